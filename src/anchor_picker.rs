@@ -0,0 +1,92 @@
+use pixels_graphics_lib::buffer_graphics_lib::Graphics;
+use pixels_graphics_lib::prelude::{
+    fill, stroke, Color, Coord, Rect, Shape, Timing, DARK_GRAY, LIGHT_GRAY, WHITE,
+};
+use pixels_graphics_lib::ui::{PixelView, ViewState};
+use pixels_graphics_lib::MouseData;
+
+use fontpad::glyph::Anchor;
+
+const CELL_SIZE: usize = 12;
+const SELECTED_COLOR: Color = WHITE;
+const HOVER_COLOR: Color = LIGHT_GRAY;
+const BORDER_COLOR: Color = DARK_GRAY;
+
+/// 3x3 grid for choosing which part of a glyph stays in place when resizing
+#[derive(Debug)]
+pub struct AnchorPicker {
+    bounds: Rect,
+    anchor: Anchor,
+}
+
+impl AnchorPicker {
+    pub fn new(pos: Coord) -> AnchorPicker {
+        AnchorPicker {
+            bounds: Rect::new_with_size(pos, CELL_SIZE * 3, CELL_SIZE * 3),
+            anchor: Anchor::default(),
+        }
+    }
+}
+
+impl AnchorPicker {
+    pub fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    /// Returns true if the anchor was changed
+    pub fn on_mouse_click(&mut self, down_at: Coord, up_at: Coord) -> bool {
+        match (self.index_for(down_at), self.index_for(up_at)) {
+            (Some(down), Some(up)) if down == up => {
+                self.anchor = Anchor::ALL[up];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn index_for(&self, pos: Coord) -> Option<usize> {
+        (0..Anchor::ALL.len()).find(|&i| self.cell_rect(i).contains(pos))
+    }
+
+    fn cell_rect(&self, idx: usize) -> Rect {
+        Rect::new_with_size(
+            self.bounds.top_left() + ((idx % 3) * CELL_SIZE, (idx / 3) * CELL_SIZE),
+            CELL_SIZE - 1,
+            CELL_SIZE - 1,
+        )
+    }
+}
+
+impl PixelView for AnchorPicker {
+    fn set_position(&mut self, top_left: Coord) {
+        self.bounds = self.bounds.move_to(top_left);
+    }
+
+    fn bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    fn render(&self, graphics: &mut Graphics, mouse: &MouseData) {
+        let hovered = self.index_for(mouse.xy);
+        for (i, anchor) in Anchor::ALL.iter().enumerate() {
+            let rect = self.cell_rect(i);
+            if *anchor == self.anchor {
+                graphics.draw_rect(rect, fill(SELECTED_COLOR));
+            } else if hovered == Some(i) {
+                graphics.draw_rect(rect, stroke(HOVER_COLOR));
+            } else {
+                graphics.draw_rect(rect, stroke(BORDER_COLOR));
+            }
+        }
+    }
+
+    fn update(&mut self, _: &Timing) {}
+
+    fn set_state(&mut self, _: ViewState) {
+        unimplemented!()
+    }
+
+    fn get_state(&self) -> ViewState {
+        ViewState::Normal
+    }
+}
@@ -0,0 +1,383 @@
+use crate::font::{Font, Metrics};
+use crate::glyph::{Glyph, MAX_SIZE};
+use crate::guide::default_layers;
+use crate::project;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const RESOLUTION: usize = 75;
+
+/// Convert `font` to Glyph Bitmap Distribution Format, `name` is used as the FONT and FAMILY_NAME
+///
+/// Rows below the [Metrics] baseline are placed under the BDF baseline, if the font has metrics
+/// they're included as FONT_ASCENT, FONT_DESCENT, CAP_HEIGHT and X_HEIGHT
+pub fn write(font: &Font, name: &str) -> String {
+    let width = font
+        .glyphs
+        .values()
+        .map(|g| g.width)
+        .max()
+        .unwrap_or(font.width);
+    let height = font
+        .glyphs
+        .values()
+        .map(|g| g.height)
+        .max()
+        .unwrap_or(font.height);
+    let name = name.replace(['-', ' '], "_");
+    let metrics = font.metrics();
+    // rows below the baseline in a glyph `height` tall, as a BDF y offset
+    let offset_y = |height: usize| metrics.baseline as isize + 1 - height as isize;
+
+    let mut output = String::new();
+    let _ = writeln!(output, "STARTFONT 2.1");
+    let _ = writeln!(
+        output,
+        "FONT -fontpad-{name}-Medium-R-Normal--{height}-{}-{RESOLUTION}-{RESOLUTION}-C-{}-ISO10646-1",
+        height * 10,
+        width * 10
+    );
+    let _ = writeln!(output, "SIZE {height} {RESOLUTION} {RESOLUTION}");
+    let _ = writeln!(
+        output,
+        "FONTBOUNDINGBOX {width} {height} 0 {}",
+        offset_y(height)
+    );
+    let _ = writeln!(
+        output,
+        "STARTPROPERTIES {}",
+        if font.metrics.is_some() { 5 } else { 3 }
+    );
+    // quotes in BDF strings are escaped by doubling them
+    let _ = writeln!(output, "FAMILY_NAME \"{}\"", name.replace('"', "\"\""));
+    if font.metrics.is_some() {
+        let _ = writeln!(output, "FONT_ASCENT {}", metrics.ascent);
+        let _ = writeln!(output, "FONT_DESCENT {}", metrics.descent);
+        let _ = writeln!(output, "CAP_HEIGHT {}", metrics.cap_height);
+        let _ = writeln!(output, "X_HEIGHT {}", metrics.x_height);
+    } else {
+        let _ = writeln!(output, "FONT_ASCENT {height}");
+        let _ = writeln!(output, "FONT_DESCENT 0");
+    }
+    let _ = writeln!(output, "ENDPROPERTIES");
+    let _ = writeln!(output, "CHARS {}", font.glyphs.len());
+    for (&chr, glyph) in &font.glyphs {
+        let _ = writeln!(output, "STARTCHAR uni{:04X}", chr as u32);
+        let _ = writeln!(output, "ENCODING {}", chr as u32);
+        let _ = writeln!(
+            output,
+            "SWIDTH {} 0",
+            glyph.width * 1000 * 72 / (height * RESOLUTION)
+        );
+        let _ = writeln!(output, "DWIDTH {} 0", glyph.width);
+        let _ = writeln!(
+            output,
+            "BBX {} {} 0 {}",
+            glyph.width,
+            glyph.height,
+            offset_y(glyph.height)
+        );
+        let _ = writeln!(output, "BITMAP");
+        let bytes_per_row = glyph.width.div_ceil(8);
+        for row in glyph.dots.chunks(glyph.width) {
+            let mut bytes = vec![0_u8; bytes_per_row];
+            for (x, _) in row.iter().enumerate().filter(|(_, &dot)| dot) {
+                bytes[x / 8] |= 0x80 >> (x % 8);
+            }
+            for byte in bytes {
+                let _ = write!(output, "{byte:02X}");
+            }
+            output.push('\n');
+        }
+        let _ = writeln!(output, "ENDCHAR");
+    }
+    let _ = writeln!(output, "ENDFONT");
+    output
+}
+
+/// Bounding box from FONTBOUNDINGBOX or BBX
+#[derive(Debug, Clone, Copy, Default)]
+struct BoundingBox {
+    width: usize,
+    height: usize,
+    x: isize,
+    y: isize,
+}
+
+impl BoundingBox {
+    fn parse(values: &[&str]) -> Result<BoundingBox> {
+        if values.len() != 4 {
+            bail!("Bounding box must have 4 values, found {}", values.len());
+        }
+        Ok(BoundingBox {
+            width: values[0].parse()?,
+            height: values[1].parse()?,
+            x: values[2].parse()?,
+            y: values[3].parse()?,
+        })
+    }
+}
+
+/// Parse a Glyph Bitmap Distribution Format font
+///
+/// Every glyph is as tall as the FONTBOUNDINGBOX and as wide as its DWIDTH (or BBX if wider),
+/// glyphs with no or negative encodings are ignored.
+/// Metrics are read from the bounding box and FONT_ASCENT, FONT_DESCENT, CAP_HEIGHT and X_HEIGHT
+pub fn parse(text: &str) -> Result<Font> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    let mut font_bounds = None;
+    let mut glyphs = BTreeMap::new();
+    let mut properties: BTreeMap<&str, usize> = BTreeMap::new();
+
+    match lines.next() {
+        Some((_, line)) if line.starts_with("STARTFONT") => {}
+        _ => bail!("Not a BDF file, missing STARTFONT"),
+    }
+
+    while let Some((line_num, line)) = lines.next() {
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("FONTBOUNDINGBOX") => {
+                font_bounds = Some(
+                    BoundingBox::parse(&parts.collect::<Vec<_>>())
+                        .with_context(|| format!("Invalid FONTBOUNDINGBOX on line {line_num}"))?,
+                )
+            }
+            Some("STARTCHAR") => {
+                let Some(font_bounds) = font_bounds else {
+                    bail!("STARTCHAR before FONTBOUNDINGBOX on line {line_num}");
+                };
+                let mut encoding = None;
+                let mut dwidth = None;
+                let mut bounds = None;
+                let mut rows = vec![];
+                let mut in_bitmap = false;
+                loop {
+                    let Some((line_num, line)) = lines.next() else {
+                        bail!("Missing ENDCHAR for glyph starting on line {line_num}");
+                    };
+                    let mut parts = line.split_whitespace();
+                    match parts.next() {
+                        Some("ENCODING") => {
+                            encoding = parts.next().and_then(|v| v.parse::<i64>().ok());
+                        }
+                        Some("DWIDTH") => {
+                            dwidth = parts.next().and_then(|v| v.parse::<usize>().ok());
+                        }
+                        Some("BBX") => {
+                            bounds = Some(
+                                BoundingBox::parse(&parts.collect::<Vec<_>>())
+                                    .with_context(|| format!("Invalid BBX on line {line_num}"))?,
+                            );
+                        }
+                        Some("BITMAP") => in_bitmap = true,
+                        Some("ENDCHAR") => break,
+                        Some(hex) if in_bitmap => rows.push(
+                            parse_row(hex)
+                                .with_context(|| format!("Invalid bitmap on line {line_num}"))?,
+                        ),
+                        _ => {}
+                    }
+                }
+                let Some(chr) = encoding
+                    .and_then(|code| u32::try_from(code).ok())
+                    .and_then(char::from_u32)
+                else {
+                    continue;
+                };
+                let bounds = bounds.unwrap_or(font_bounds);
+                let glyph = create_glyph(font_bounds, bounds, dwidth, &rows)
+                    .with_context(|| format!("Unable to import {chr:?}"))?;
+                glyphs.insert(chr, glyph);
+            }
+            Some(name @ ("FONT_ASCENT" | "FONT_DESCENT" | "CAP_HEIGHT" | "X_HEIGHT")) => {
+                if let Some(value) = parts.next().and_then(|v| v.parse().ok()) {
+                    properties.insert(name, value);
+                }
+            }
+            Some("ENDFONT") => break,
+            _ => {}
+        }
+    }
+
+    let Some(font_bounds) = font_bounds else {
+        bail!("Missing FONTBOUNDINGBOX");
+    };
+    if glyphs.is_empty() {
+        bail!("No glyphs found");
+    }
+
+    let height = font_bounds.height.max(1);
+    let above_baseline = (font_bounds.height as isize + font_bounds.y).max(0) as usize;
+    let defaults = Metrics::new(above_baseline);
+    let metrics = Metrics {
+        baseline: above_baseline.saturating_sub(1).min(height - 1),
+        ascent: properties
+            .get("FONT_ASCENT")
+            .copied()
+            .unwrap_or(above_baseline),
+        descent: properties
+            .get("FONT_DESCENT")
+            .copied()
+            .unwrap_or((-font_bounds.y).max(0) as usize),
+        cap_height: properties
+            .get("CAP_HEIGHT")
+            .copied()
+            .unwrap_or(defaults.cap_height),
+        x_height: properties
+            .get("X_HEIGHT")
+            .copied()
+            .unwrap_or(defaults.x_height),
+    };
+
+    let font = Font {
+        width: font_bounds.width,
+        height: font_bounds.height,
+        glyphs,
+        metrics: Some(metrics),
+        guide_layers: default_layers(),
+    };
+    project::validate(&font)?;
+    Ok(font)
+}
+
+fn parse_row(hex: &str) -> Result<Vec<bool>> {
+    let mut output = Vec::with_capacity(hex.len() * 4);
+    for chr in hex.chars() {
+        let Some(value) = chr.to_digit(16) else {
+            bail!("'{chr}' is not hex");
+        };
+        for bit in (0..4).rev() {
+            output.push(value & (1 << bit) != 0);
+        }
+    }
+    Ok(output)
+}
+
+/// Place the glyph bitmap in a grid as tall as the font, with the baseline at the font's baseline
+///
+/// Fails if the grid would be larger than [MAX_SIZE]
+fn create_glyph(
+    font_bounds: BoundingBox,
+    bounds: BoundingBox,
+    dwidth: Option<usize>,
+    rows: &[Vec<bool>],
+) -> Result<Glyph> {
+    let offset_x = bounds.x - font_bounds.x;
+    // the grid starts at the font's x offset, so the advance covers that many more (or fewer) columns
+    let advance = dwidth.unwrap_or(bounds.width) as isize - font_bounds.x;
+    let width = advance.max(offset_x + bounds.width as isize).max(1) as usize;
+    let height = font_bounds.height.max(1);
+    if width > MAX_SIZE || height > MAX_SIZE {
+        bail!("Glyph is {width}x{height}, the max is {MAX_SIZE}x{MAX_SIZE}");
+    }
+    let top = (font_bounds.y + font_bounds.height as isize) - (bounds.y + bounds.height as isize);
+    let mut glyph = Glyph::new(width, height);
+    for (y, row) in rows.iter().take(bounds.height).enumerate() {
+        for (x, _) in row
+            .iter()
+            .take(bounds.width)
+            .enumerate()
+            .filter(|(_, &dot)| dot)
+        {
+            let gx = x as isize + offset_x;
+            let gy = y as isize + top;
+            if (0..width as isize).contains(&gx) && (0..height as isize).contains(&gy) {
+                glyph.dots[gx as usize + gy as usize * width] = true;
+            }
+        }
+    }
+    Ok(glyph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glyph::Region;
+
+    /// Glyphs of different widths, one wider than a byte and one outside ASCII
+    fn font() -> Font {
+        let mut narrow = Glyph::new(1, 2);
+        narrow.dots = vec![true, false];
+        let mut wide = Glyph::new(10, 2);
+        wide.dots[0] = true;
+        wide.dots[19] = true;
+        let mut euro = Glyph::new(3, 2);
+        euro.dots[1] = true;
+        Font {
+            glyphs: BTreeMap::from([('i', narrow), ('W', wide), ('€', euro)]),
+            ..Font::new(3, 2)
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_glyph_widths() {
+        let font = font();
+        let parsed = parse(&write(&font, "test")).unwrap();
+        assert_eq!(parsed.glyphs, font.glyphs);
+    }
+
+    #[test]
+    fn metrics_round_trip() {
+        let mut font = font();
+        font.metrics = Some(Metrics {
+            baseline: 0,
+            ascent: 1,
+            descent: 1,
+            cap_height: 1,
+            x_height: 1,
+        });
+        let parsed = parse(&write(&font, "test")).unwrap();
+        assert_eq!(parsed.metrics, font.metrics);
+        assert_eq!(parsed.glyphs, font.glyphs);
+    }
+
+    #[test]
+    fn bitmap_is_placed_by_its_bounding_box() {
+        let text = "STARTFONT 2.1\nFONTBOUNDINGBOX 4 4 0 -1\nSTARTCHAR period\nENCODING 46\nDWIDTH 2 0\nBBX 1 1 1 0\nBITMAP\n80\nENDCHAR\nENDFONT\n";
+        let glyph = &parse(text).unwrap().glyphs[&'.'];
+        assert_eq!((glyph.width, glyph.height), (2, 4));
+        assert_eq!(
+            glyph.dots,
+            [false, false, false, false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn negative_font_offset_widens_glyphs() {
+        let text = "STARTFONT 2.1\nFONTBOUNDINGBOX 4 4 -1 0\nSTARTCHAR j\nENCODING 106\nDWIDTH 2 0\nBBX 1 1 -1 0\nBITMAP\n80\nENDCHAR\nENDFONT\n";
+        let glyph = &parse(text).unwrap().glyphs[&'j'];
+        assert_eq!((glyph.width, glyph.height), (3, 4));
+        assert_eq!(glyph.content_bounds(), Some(Region::new(0, 3, 1, 1)));
+    }
+
+    #[test]
+    fn family_name_quotes_are_escaped() {
+        let text = write(&font(), "say \"hi\"");
+        assert!(text.contains("FAMILY_NAME \"say_\"\"hi\"\"\"\n"));
+    }
+
+    fn bdf(bounds: &str, dwidth: usize) -> String {
+        format!(
+            "STARTFONT 2.1\nFONTBOUNDINGBOX {bounds}\nSTARTCHAR A\nENCODING 65\nDWIDTH {dwidth} 0\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\nENDFONT\n"
+        )
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert!(parse(&bdf("8 8 0 0", 8)).is_ok());
+        assert!(parse(&bdf("0 8 0 0", 8)).is_err());
+        assert!(parse(&bdf("8 8 0 0", MAX_SIZE + 1)).is_err());
+        assert!(parse(&bdf("8 100 0 0", 8)).is_err());
+    }
+
+    #[test]
+    fn requires_bounding_box() {
+        assert!(parse("STARTFONT 2.1\nENDFONT\n").is_err());
+        assert!(parse("FONTBOUNDINGBOX 1 1 0 0\n").is_err());
+    }
+}
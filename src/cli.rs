@@ -0,0 +1,140 @@
+use anyhow::{bail, Context, Result};
+use fontpad::format::Format;
+use fontpad::packed::{BitOrder, PackedFormat, PixelOrder, WordSize};
+use std::fs;
+use std::io::{stdin, stdout, Read, Write};
+use std::path::Path;
+
+const USAGE: &str = "Usage:
+  fontpad                                  Open the editor
+  fontpad convert <INPUT> <OUTPUT> [OPTIONS]
+  fontpad help
+
+INPUT is a .fontpad project, .bdf or .rs file, use - for stdin (--from is then required)
+OUTPUT is the file to write, use - for stdout (--to is then required)
+
+Options:
+  --from <FORMAT>            Input format, needed if the extension isn't recognised
+  --to <FORMAT>              Output format, needed if the extension isn't recognised
+  --name <NAME>              BDF font name, defaults to the output file name
+  --word <u8|u16|u32>        Packed word size, defaults to u8
+  --order <row|column|pages> Packed pixel order, defaults to row
+  --bits <msb|lsb>           Packed bit order, defaults to msb
+  --no-pad                   Don't start each packed row on a new word
+
+Formats:
+  fontpad      Project (.fontpad)
+  bdf          Glyph Bitmap Distribution Format (.bdf)
+  rust         buffer-graphics-lib font module (.rs)
+  packed-rust  Packed arrays as Rust
+  packed-c     Packed arrays as C (.h, .c)
+  hex          Packed arrays as hex (.hex)
+";
+
+#[derive(Debug, Default)]
+struct ConvertArgs {
+    input: String,
+    output: String,
+    from: Option<Format>,
+    to: Option<Format>,
+    name: Option<String>,
+    packed: PackedFormat,
+}
+
+/// Run the command in `args` (not including the program name) without opening a window
+pub fn run(args: &[String]) -> Result<()> {
+    match args[0].as_str() {
+        "convert" => convert(parse_convert_args(&args[1..])?),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        command => bail!("Unknown command '{command}'\n\n{USAGE}"),
+    }
+}
+
+fn parse_convert_args(args: &[String]) -> Result<ConvertArgs> {
+    let mut output = ConvertArgs::default();
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--from" => output.from = Some(Format::from_name(value()?)?),
+            "--to" => output.to = Some(Format::from_name(value()?)?),
+            "--name" => output.name = Some(value()?.clone()),
+            "--word" => {
+                output.packed.word_size = match value()?.as_str() {
+                    "u8" => WordSize::U8,
+                    "u16" => WordSize::U16,
+                    "u32" => WordSize::U32,
+                    other => bail!("Unknown word size '{other}'"),
+                }
+            }
+            "--order" => {
+                output.packed.order = match value()?.as_str() {
+                    "row" => PixelOrder::RowMajor,
+                    "column" => PixelOrder::ColumnMajor,
+                    "pages" => PixelOrder::Pages,
+                    other => bail!("Unknown pixel order '{other}'"),
+                }
+            }
+            "--bits" => {
+                output.packed.bit_order = match value()?.as_str() {
+                    "msb" => BitOrder::MsbFirst,
+                    "lsb" => BitOrder::LsbFirst,
+                    other => bail!("Unknown bit order '{other}'"),
+                }
+            }
+            "--no-pad" => output.packed.pad_rows = false,
+            flag if flag.starts_with("--") => bail!("Unknown option '{flag}'\n\n{USAGE}"),
+            path => paths.push(path.to_string()),
+        }
+    }
+    let [input, path] = paths.as_slice() else {
+        bail!("convert needs an input and output path\n\n{USAGE}");
+    };
+    output.input = input.clone();
+    output.output = path.clone();
+    Ok(output)
+}
+
+fn convert(args: ConvertArgs) -> Result<()> {
+    let Some(from) = args.from.or_else(|| Format::from_path(&args.input)) else {
+        bail!("Unable to tell format of {}, use --from", args.input);
+    };
+    let Some(to) = args.to.or_else(|| Format::from_path(&args.output)) else {
+        bail!("Unable to tell format of {}, use --to", args.output);
+    };
+
+    let text = if args.input == "-" {
+        let mut text = String::new();
+        stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(&args.input).with_context(|| format!("Unable to read {}", args.input))?
+    };
+    let font = from
+        .load(&text)
+        .with_context(|| format!("Unable to load {}", args.input))?;
+
+    let name = args.name.clone().unwrap_or_else(|| {
+        Path::new(&args.output)
+            .file_stem()
+            .filter(|_| args.output != "-")
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("fontpad"))
+    });
+    let output = to.save(&font, &name, args.packed)?;
+
+    if args.output == "-" {
+        stdout().write_all(output.as_bytes())?;
+    } else {
+        fs::write(&args.output, output)
+            .with_context(|| format!("Unable to write {}", args.output))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,362 @@
+use crate::glyph::{resizer, Anchor, Glyph, MAX_SIZE};
+use crate::guide::{default_layers, GuideLayer};
+use buffer_graphics_lib::text::{chr_to_code, PixelFont};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Symbols outside of ASCII that buffer-graphics-lib fonts support
+pub const EXTRA_CHARS: [char; 8] = ['£', '¥', '¢', '¤', '€', '°', '…', '✓'];
+
+/// Printable ASCII followed by [EXTRA_CHARS]
+pub fn default_charset() -> Vec<char> {
+    (' '..='~').chain(EXTRA_CHARS).collect()
+}
+
+/// Font-wide vertical metrics, heights are counted in rows up from and including the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// Row (from the top) that letters without descenders end on
+    pub baseline: usize,
+    /// Rows used above the baseline by the tallest glyphs
+    pub ascent: usize,
+    /// Rows used below the baseline by descenders
+    pub descent: usize,
+    /// Height of capital letters
+    pub cap_height: usize,
+    /// Height of lowercase letters without ascenders
+    pub x_height: usize,
+}
+
+impl Metrics {
+    /// Metrics for glyphs `height` rows tall with no descenders
+    pub fn new(height: usize) -> Metrics {
+        Metrics {
+            baseline: height.saturating_sub(1),
+            ascent: height,
+            descent: 0,
+            cap_height: height,
+            x_height: (height * 2).div_ceil(3),
+        }
+    }
+
+    /// Top row (from the top) of something `height` rows tall standing on the baseline, negative if it doesn't fit
+    pub fn row_for_height(&self, height: usize) -> isize {
+        self.baseline as isize + 1 - height as isize
+    }
+}
+
+/// A font project, every character in the set has a glyph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Font {
+    /// Default glyph width, used for new glyphs
+    pub width: usize,
+    /// Default glyph height, used for new glyphs
+    pub height: usize,
+    pub glyphs: BTreeMap<char, Glyph>,
+    /// None until set, see [Font::metrics]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Metrics>,
+    /// Every glyph has a list of guides for each layer
+    #[serde(default = "default_layers")]
+    pub guide_layers: Vec<GuideLayer>,
+}
+
+impl Font {
+    pub fn new(width: usize, height: usize) -> Font {
+        Font {
+            width,
+            height,
+            glyphs: default_charset()
+                .into_iter()
+                .map(|chr| (chr, Glyph::new(width, height)))
+                .collect(),
+            metrics: None,
+            guide_layers: default_layers(),
+        }
+    }
+
+    /// Metrics set for the font, or the default for its height
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.unwrap_or_else(|| Metrics::new(self.height))
+    }
+}
+
+impl Font {
+    /// Copy of a buffer-graphics-lib font, characters it doesn't support are left empty
+    pub fn from_pixel_font(pixel_font: PixelFont) -> Font {
+        let (width, height) = pixel_font.size();
+        let unknown = pixel_font.pixels(0);
+        let mut font = Font::new(width, height);
+        for (&chr, glyph) in font.glyphs.iter_mut() {
+            let code = chr_to_code(chr);
+            let pixels = pixel_font.pixels(code);
+            if chr == ' ' || code == 0 || pixels == unknown || pixels.len() != glyph.dots.len() {
+                continue;
+            }
+            glyph.dots = pixels.to_vec();
+        }
+        font
+    }
+}
+
+impl Font {
+    /// Returns the glyph for `chr` or a new empty glyph at the default size
+    ///
+    /// The glyph has guides for every layer, with the marks from shared layers copied in.
+    /// Shared layers only cover the default size, outside of it the glyph keeps its own marks
+    pub fn glyph_or_new(&self, chr: char) -> Glyph {
+        let mut glyph = self
+            .glyphs
+            .get(&chr)
+            .cloned()
+            .unwrap_or_else(|| Glyph::new(self.width, self.height));
+        let size = glyph.size();
+        glyph
+            .guides
+            .resize(self.guide_layers.len(), vec![false; size.0 * size.1]);
+        for (layer, guides) in self.guide_layers.iter().zip(&mut glyph.guides) {
+            if layer.shared {
+                for (x, y) in shared_cells(self.size(), size) {
+                    guides[x + y * size.0] = layer.cells[x + y * self.width];
+                }
+            }
+        }
+        glyph
+    }
+
+    /// Store `glyph` for `chr`, marks on shared layers are stored in the layer instead of the glyph
+    ///
+    /// Shared marks outside the default size stay on the glyph, see [Font::glyph_or_new]
+    pub fn set_glyph(&mut self, chr: char, mut glyph: Glyph) {
+        let previous = self.glyphs.get(&chr);
+        let cells = shared_cells(self.size(), glyph.size());
+        let width = self.width;
+        for (i, layer) in self.guide_layers.iter_mut().enumerate() {
+            let Some(guides) = glyph.guides.get_mut(i).filter(|_| layer.shared) else {
+                continue;
+            };
+            // keep the glyph's own marks in case the layer stops being shared
+            let own = previous
+                .and_then(|previous| previous.guides.get(i))
+                .filter(|previous| previous.len() == guides.len());
+            for &(x, y) in &cells {
+                let idx = x + y * glyph.width;
+                layer.cells[x + y * width] = guides[idx];
+                guides[idx] = own.map(|own| own[idx]).unwrap_or_default();
+            }
+        }
+        self.glyphs.insert(chr, glyph);
+    }
+
+    /// Default glyph size
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Grow or shrink the default size and every glyph by `width` and `height` cells, keeping content at `anchor`
+    ///
+    /// Sizes stay between 1 and [MAX_SIZE], shared guide layers and metrics move with the content
+    pub fn resize_by(&mut self, width: isize, height: isize, anchor: Anchor) {
+        let change = |size: usize, by: isize| size.saturating_add_signed(by).clamp(1, MAX_SIZE);
+        let old_size = self.size();
+        let new_size = (change(self.width, width), change(self.height, height));
+        for glyph in self.glyphs.values_mut() {
+            glyph.resize(
+                change(glyph.width, width),
+                change(glyph.height, height),
+                anchor,
+            );
+        }
+        let offset = anchor.offset(old_size, new_size);
+        for layer in self.guide_layers.iter_mut().filter(|layer| layer.shared) {
+            layer.cells = resizer(old_size, new_size, offset, &layer.cells);
+        }
+        if let Some(metrics) = &mut self.metrics {
+            metrics.baseline = metrics
+                .baseline
+                .saturating_add_signed(offset.1)
+                .min(new_size.1 - 1);
+            for height in [
+                &mut metrics.ascent,
+                &mut metrics.descent,
+                &mut metrics.cap_height,
+                &mut metrics.x_height,
+            ] {
+                *height = (*height).min(new_size.1);
+            }
+        }
+        (self.width, self.height) = new_size;
+    }
+
+    /// Replace the guide layers, each layer is paired with the index of the existing layer its marks are taken from
+    ///
+    /// Layers without an existing index start empty, layers that stop being shared copy the shared marks into every glyph
+    pub fn set_guide_layers(&mut self, layers: Vec<(Option<usize>, GuideLayer)>) {
+        let unshared: Vec<Option<&GuideLayer>> = layers
+            .iter()
+            .map(|(source, layer)| {
+                source
+                    .and_then(|i| self.guide_layers.get(i))
+                    .filter(|previous| previous.shared && !layer.shared)
+            })
+            .collect();
+        for glyph in self.glyphs.values_mut() {
+            let empty = vec![false; glyph.width * glyph.height];
+            let cells = shared_cells((self.width, self.height), glyph.size());
+            let mut guides: Vec<Vec<bool>> = layers
+                .iter()
+                .map(|(source, _)| {
+                    source
+                        .and_then(|i| glyph.guides.get(i).cloned())
+                        .unwrap_or_else(|| empty.clone())
+                })
+                .collect();
+            for (guides, shared) in guides.iter_mut().zip(&unshared) {
+                let Some(shared) = shared else {
+                    continue;
+                };
+                for &(x, y) in &cells {
+                    guides[x + y * glyph.width] |= shared.cells[x + y * self.width];
+                }
+            }
+            glyph.guides = guides;
+        }
+        let cell_count = self.width * self.height;
+        self.guide_layers = layers
+            .into_iter()
+            .map(|(_, mut layer)| {
+                if layer.shared {
+                    layer.cells.resize(cell_count, false);
+                } else {
+                    layer.cells.clear();
+                }
+                layer
+            })
+            .collect();
+    }
+
+    pub fn first_char(&self) -> char {
+        self.glyphs.keys().next().copied().unwrap_or(' ')
+    }
+
+    /// Character after `chr` in the set, wrapping around at the end
+    pub fn next_char(&self, chr: char) -> char {
+        self.glyphs
+            .range((std::ops::Bound::Excluded(chr), std::ops::Bound::Unbounded))
+            .next()
+            .map(|(&chr, _)| chr)
+            .unwrap_or_else(|| self.first_char())
+    }
+
+    /// Character before `chr` in the set, wrapping around at the start
+    pub fn prev_char(&self, chr: char) -> char {
+        self.glyphs
+            .range(..chr)
+            .next_back()
+            .or_else(|| self.glyphs.iter().next_back())
+            .map(|(&chr, _)| chr)
+            .unwrap_or(chr)
+    }
+}
+
+/// Cells of a glyph of `size` that are covered by shared guide layers in a font of `font_size`
+fn shared_cells(font_size: (usize, usize), size: (usize, usize)) -> Vec<(usize, usize)> {
+    (0..size.1.min(font_size.1))
+        .flat_map(|y| (0..size.0.min(font_size.0)).map(move |x| (x, y)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared_font() -> Font {
+        let mut font = Font::new(2, 2);
+        let mut layer = font.guide_layers[0].clone();
+        layer.shared = true;
+        font.set_guide_layers(vec![(Some(0), layer)]);
+        font
+    }
+
+    #[test]
+    fn shared_marks_show_on_every_glyph() {
+        let mut font = shared_font();
+        let mut glyph = font.glyph_or_new('A');
+        glyph.set_guides(0, &[(1, 0)], true);
+        font.set_glyph('A', glyph);
+        assert_eq!(
+            font.glyph_or_new('B').guides[0],
+            [false, true, false, false]
+        );
+        assert_eq!(font.glyphs[&'B'].guides[0], [false; 4]);
+    }
+
+    #[test]
+    fn shared_marks_outside_default_size_stay_on_glyph() {
+        let mut font = shared_font();
+        let mut glyph = font.glyph_or_new('A');
+        glyph.resize(3, 2, Anchor::TopLeft);
+        glyph.set_guides(0, &[(0, 0), (2, 0)], true);
+        font.set_glyph('A', glyph);
+        assert_eq!(
+            font.glyph_or_new('A').guides[0],
+            [true, false, true, false, false, false]
+        );
+        assert_eq!(
+            font.glyph_or_new('B').guides[0],
+            [true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn unsharing_copies_marks_to_every_glyph() {
+        let mut font = shared_font();
+        let mut glyph = font.glyph_or_new('A');
+        glyph.set_guides(0, &[(0, 1)], true);
+        font.set_glyph('A', glyph);
+        let mut layer = font.guide_layers[0].clone();
+        layer.shared = false;
+        font.set_guide_layers(vec![(Some(0), layer)]);
+        assert!(font.guide_layers[0].cells.is_empty());
+        for glyph in font.glyphs.values() {
+            assert_eq!(glyph.guides[0], [false, false, true, false]);
+        }
+    }
+
+    #[test]
+    fn new_layers_start_empty() {
+        let mut font = Font::new(2, 2);
+        let mut glyph = font.glyph_or_new('A');
+        glyph.set_guides(0, &[(0, 0)], true);
+        font.set_glyph('A', glyph);
+        let layers = vec![
+            (None, GuideLayer::new("New", [0, 0, 0])),
+            (Some(0), font.guide_layers[0].clone()),
+        ];
+        font.set_guide_layers(layers);
+        assert_eq!(font.glyphs[&'A'].guides[0], [false; 4]);
+        assert_eq!(font.glyphs[&'A'].guides[1], [true, false, false, false]);
+    }
+
+    #[test]
+    fn resize_by_changes_every_glyph_and_default_size() {
+        let mut font = shared_font();
+        font.glyphs.insert('a', Glyph::new(1, 2));
+        let mut glyph = font.glyph_or_new('A');
+        glyph.set_guides(0, &[(0, 0)], true);
+        glyph.set_dots(&[(1, 1)], true);
+        font.set_glyph('A', glyph);
+        font.metrics = Some(Metrics::new(2));
+
+        font.resize_by(1, -1, Anchor::BottomRight);
+        assert_eq!(font.size(), (3, 1));
+        assert_eq!(font.glyphs[&'A'].dots, [false, false, true]);
+        assert_eq!(font.glyphs[&'a'].size(), (2, 1));
+        assert_eq!(font.guide_layers[0].cells, [false; 3]);
+        assert_eq!(font.metrics, Some(Metrics::new(1)));
+
+        font.resize_by(-4, 0, Anchor::TopLeft);
+        assert_eq!(font.size(), (1, 1));
+        assert_eq!(font.glyphs[&'a'].size(), (1, 1));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::font::Font;
+use crate::packed::{Output, PackedFormat};
+use crate::{bdf, packed, project, rust_source};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// File formats fonts can be converted to or from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Project,
+    Bdf,
+    Rust,
+    Packed(Output),
+}
+
+impl Format {
+    /// Parse a format name as used by the command line, e.g. `bdf` or `packed-c`
+    pub fn from_name(name: &str) -> Result<Format> {
+        Ok(match name {
+            "fontpad" => Format::Project,
+            "bdf" => Format::Bdf,
+            "rust" => Format::Rust,
+            "packed-rust" => Format::Packed(Output::Rust),
+            "packed-c" => Format::Packed(Output::C),
+            "hex" => Format::Packed(Output::Hex),
+            _ => bail!("Unknown format '{name}'"),
+        })
+    }
+
+    /// Guess the format from the extension of `path`
+    pub fn from_path(path: &str) -> Option<Format> {
+        let ext = Path::new(path)
+            .extension()?
+            .to_string_lossy()
+            .to_lowercase();
+        match ext.as_str() {
+            project::EXTENSION => Some(Format::Project),
+            "bdf" => Some(Format::Bdf),
+            "rs" => Some(Format::Rust),
+            "h" | "c" => Some(Format::Packed(Output::C)),
+            "hex" => Some(Format::Packed(Output::Hex)),
+            _ => None,
+        }
+    }
+
+    /// Parse `text` as this format, packed formats can't be loaded
+    pub fn load(&self, text: &str) -> Result<Font> {
+        match self {
+            Format::Project => project::parse(text),
+            Format::Bdf => bdf::parse(text),
+            Format::Rust => rust_source::parse(text),
+            Format::Packed(_) => bail!("Packed arrays can only be exported"),
+        }
+    }
+
+    /// Convert `font` to this format, `name` is only used by BDF and `packed` by packed formats
+    pub fn save(&self, font: &Font, name: &str, packed: PackedFormat) -> Result<String> {
+        match self {
+            Format::Project => project::write(font),
+            Format::Bdf => Ok(bdf::write(font, name)),
+            Format::Rust => rust_source::write(font),
+            Format::Packed(output) => Ok(packed::write(
+                font,
+                &PackedFormat {
+                    output: *output,
+                    ..packed
+                },
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_extensions() {
+        assert_eq!(
+            Format::from_name("packed-c").unwrap(),
+            Format::Packed(Output::C)
+        );
+        assert!(Format::from_name("png").is_err());
+        assert_eq!(Format::from_path("font.FONTPAD"), Some(Format::Project));
+        assert_eq!(Format::from_path("font.rs"), Some(Format::Rust));
+        assert_eq!(Format::from_path("font"), None);
+    }
+
+    #[test]
+    fn packed_can_only_be_saved() {
+        let font = Font::new(2, 2);
+        let format = Format::Packed(Output::Hex);
+        assert!(format.load("").is_err());
+        assert!(format
+            .save(&font, "test", PackedFormat::default())
+            .unwrap()
+            .starts_with("0020:"));
+    }
+}
@@ -0,0 +1,731 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Max width and height of a glyph in cells
+pub const MAX_SIZE: usize = 64;
+
+/// Which part of a glyph stays in place when it's resized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    /// Keeps the baseline and left edge in place
+    #[default]
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// All anchors, in reading order of a 3x3 grid
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    /// Returns the amount existing content should move by when resizing from `old` to `new`
+    pub fn offset(&self, old: (usize, usize), new: (usize, usize)) -> (isize, isize) {
+        let idx = Anchor::ALL
+            .iter()
+            .position(|a| a == self)
+            .unwrap_or_default();
+        let diff = (
+            new.0 as isize - old.0 as isize,
+            new.1 as isize - old.1 as isize,
+        );
+        let offset = |diff: isize, pos: usize| match pos {
+            0 => 0,
+            1 => diff / 2,
+            _ => diff,
+        };
+        (offset(diff.0, idx % 3), offset(diff.1, idx / 3))
+    }
+}
+
+/// Rectangle of cells in a glyph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Region covering both cells and everything between them
+    pub fn from_corners(a: (usize, usize), b: (usize, usize)) -> Region {
+        let x = a.0.min(b.0);
+        let y = a.1.min(b.1);
+        Region::new(x, y, a.0.max(b.0) - x + 1, a.1.max(b.1) - y + 1)
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Returns the part of this region inside a grid of `size`, or None if it's completely outside
+    pub fn clamp_to(&self, size: (usize, usize)) -> Option<Region> {
+        if self.x >= size.0 || self.y >= size.1 || self.width == 0 || self.height == 0 {
+            return None;
+        }
+        Some(Region::new(
+            self.x,
+            self.y,
+            self.width.min(size.0 - self.x),
+            self.height.min(size.1 - self.y),
+        ))
+    }
+}
+
+/// How [Glyph::shift] moves cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShiftOptions {
+    /// Cells moved past an edge come back on the opposite edge, otherwise they're removed
+    pub wrap: bool,
+    /// Move guides as well as dots
+    pub guides: bool,
+}
+
+/// Shift dots only, removing any moved past the edges
+const NO_WRAP: ShiftOptions = ShiftOptions {
+    wrap: false,
+    guides: false,
+};
+
+impl Default for ShiftOptions {
+    fn default() -> Self {
+        ShiftOptions {
+            wrap: true,
+            guides: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Glyph {
+    pub width: usize,
+    pub height: usize,
+    pub dots: Vec<bool>,
+    /// Marks for each of the font's guide layers
+    #[serde(deserialize_with = "deserialize_guides")]
+    pub guides: Vec<Vec<bool>>,
+}
+
+/// Guides from before layers are a single list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GuidesFile {
+    Layers(Vec<Vec<bool>>),
+    Single(Vec<bool>),
+}
+
+fn deserialize_guides<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<Vec<bool>>, D::Error> {
+    Ok(match GuidesFile::deserialize(deserializer)? {
+        GuidesFile::Layers(layers) => layers,
+        GuidesFile::Single(guides) => vec![guides],
+    })
+}
+
+impl Glyph {
+    /// Empty glyph with one guide layer
+    pub fn new(width: usize, height: usize) -> Glyph {
+        Glyph {
+            width,
+            height,
+            dots: vec![false; width * height],
+            guides: vec![vec![false; width * height]],
+        }
+    }
+}
+
+impl Glyph {
+    /// Returns true if no pixels are set (guides are ignored)
+    pub fn is_empty(&self) -> bool {
+        !self.dots.iter().any(|&dot| dot)
+    }
+}
+
+impl Glyph {
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Change the size, keeping existing dots and guides positioned relative to `anchor`
+    pub fn resize(&mut self, width: usize, height: usize, anchor: Anchor) {
+        let new_size = (width, height);
+        let offset = anchor.offset(self.size(), new_size);
+        self.dots = resizer(self.size(), new_size, offset, &self.dots);
+        self.guides = self
+            .guides
+            .iter()
+            .map(|guides| resizer(self.size(), new_size, offset, guides))
+            .collect();
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn clear(&mut self) {
+        self.dots.fill(false);
+    }
+
+    /// Unset guides on every layer
+    pub fn clear_guides(&mut self) {
+        self.guides.iter_mut().for_each(|guides| guides.fill(false));
+    }
+
+    pub fn fill(&mut self) {
+        self.dots.fill(true);
+    }
+
+    /// Swap set and unset dots
+    pub fn invert(&mut self) {
+        self.dots.iter_mut().for_each(|dot| *dot = !*dot);
+    }
+
+    /// Set the dots at `cells` to `value`, cells outside the glyph are ignored
+    pub fn set_dots(&mut self, cells: &[(usize, usize)], value: bool) {
+        for &(x, y) in cells {
+            if x < self.width && y < self.height {
+                self.dots[x + y * self.width] = value;
+            }
+        }
+    }
+
+    /// Set the guides on `layer` at `cells` to `value`, cells outside the glyph are ignored
+    pub fn set_guides(&mut self, layer: usize, cells: &[(usize, usize)], value: bool) {
+        let Some(guides) = self.guides.get_mut(layer) else {
+            return;
+        };
+        for &(x, y) in cells {
+            if x < self.width && y < self.height {
+                guides[x + y * self.width] = value;
+            }
+        }
+    }
+
+    pub fn flip_h(&mut self) {
+        self.dots = horz_swapper(self.width, &self.dots);
+    }
+
+    pub fn flip_v(&mut self) {
+        self.dots = vert_swapper(self.width, self.height, &self.dots);
+    }
+
+    /// Turn dots and guides a quarter turn clockwise, swapping width and height
+    pub fn rotate_cw(&mut self) {
+        let height = self.height;
+        self.remap((self.height, self.width), |x, y| (height - 1 - y, x));
+    }
+
+    /// Turn dots and guides a quarter turn counter-clockwise, swapping width and height
+    pub fn rotate_ccw(&mut self) {
+        let width = self.width;
+        self.remap((self.height, self.width), |x, y| (y, width - 1 - x));
+    }
+
+    /// Mirror dots and guides along the diagonal from the top left, swapping width and height
+    pub fn transpose(&mut self) {
+        self.remap((self.height, self.width), |x, y| (y, x));
+    }
+
+    /// Slant dots and guides to the right, the bottom row stays in place and every `rows_per_cell` rows above it move one more cell right
+    ///
+    /// Cells moved past the right edge are removed
+    pub fn shear(&mut self, rows_per_cell: usize) {
+        let rows_per_cell = rows_per_cell.max(1);
+        let (width, height) = self.size();
+        let shear_cells = |cells: &[bool]| {
+            let mut output = vec![false; cells.len()];
+            for y in 0..height {
+                let offset = (height - 1 - y) / rows_per_cell;
+                for x in offset..width {
+                    output[x + y * width] = cells[x - offset + y * width];
+                }
+            }
+            output
+        };
+        self.dots = shear_cells(&self.dots);
+        self.guides = self
+            .guides
+            .iter()
+            .map(|guides| shear_cells(guides))
+            .collect();
+    }
+
+    /// Move every dot and guide to the position returned by `map`, and change the size to `new_size`
+    fn remap<F: Fn(usize, usize) -> (usize, usize)>(&mut self, new_size: (usize, usize), map: F) {
+        let remap_cells = |cells: &[bool]| {
+            let mut output = vec![false; new_size.0 * new_size.1];
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let (new_x, new_y) = map(x, y);
+                    output[new_x + new_y * new_size.0] = cells[x + y * self.width];
+                }
+            }
+            output
+        };
+        let dots = remap_cells(&self.dots);
+        let guides = self
+            .guides
+            .iter()
+            .map(|guides| remap_cells(guides))
+            .collect();
+        (self.width, self.height) = new_size;
+        self.dots = dots;
+        self.guides = guides;
+    }
+
+    /// Shift dots up by one row, the top row wraps to the bottom
+    pub fn move_up(&mut self) {
+        self.shift(0, -1, ShiftOptions::default());
+    }
+
+    /// Shift dots down by one row, the bottom row wraps to the top
+    pub fn move_down(&mut self) {
+        self.shift(0, 1, ShiftOptions::default());
+    }
+
+    /// Shift dots left by one column, the first column wraps to the right edge
+    pub fn move_left(&mut self) {
+        self.shift(-1, 0, ShiftOptions::default());
+    }
+
+    /// Shift dots right by one column, the last column wraps to the left edge
+    pub fn move_right(&mut self) {
+        self.shift(1, 0, ShiftOptions::default());
+    }
+
+    /// Shift dots (and guides if set in `options`) by `x` columns and `y` rows
+    pub fn shift(&mut self, x: isize, y: isize, options: ShiftOptions) {
+        let size = self.size();
+        self.dots = shifter(size, (x, y), options.wrap, &self.dots);
+        if options.guides {
+            for guides in &mut self.guides {
+                *guides = shifter(size, (x, y), options.wrap, guides);
+            }
+        }
+    }
+
+    /// Dots as a comma separated list of `true`/`false`, one row per line
+    pub fn copy_str(&self) -> String {
+        let mut output = String::new();
+        let mut i = 1;
+        for value in &self.dots {
+            output.push_str(if *value { "true" } else { "false" });
+            output.push(',');
+            if i >= self.width {
+                output.push('\n');
+                i = 0;
+            }
+            i += 1;
+        }
+        output.trim().to_string()
+    }
+
+    /// Set dots from a list made by [Glyph::copy_str], it must have a value for every dot
+    pub fn paste_str(&mut self, value: &str) -> Result<()> {
+        let parts: Vec<&str> = value.split(',').collect();
+        if parts.len() != self.dots.len() {
+            bail!(
+                "Invalid length, expected {} found {}",
+                self.dots.len(),
+                parts.len()
+            );
+        }
+        if !parts.iter().all(|s| matches!(s.trim(), "true" | "false")) {
+            bail!("Invalid string pasted must be ([true|false],){{16,576}}");
+        }
+        parts
+            .iter()
+            .enumerate()
+            .for_each(|(i, &value)| self.dots[i] = value.trim() == "true");
+        Ok(())
+    }
+
+    /// Smallest region containing every set dot, None if no dots are set
+    pub fn content_bounds(&self) -> Option<Region> {
+        let mut cells = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.dots[x + y * self.width]);
+        let first = cells.next()?;
+        let (min, max) = cells.fold((first, first), |(min, max), (x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        });
+        Some(Region::from_corners(min, max))
+    }
+
+    /// Crop dots and guides to [Glyph::content_bounds], glyphs without any dots set are unchanged
+    pub fn trim(&mut self) {
+        if let Some(region) = self.content_bounds() {
+            *self = self.copy_region(region);
+        }
+    }
+
+    /// Move the dots so the empty columns on the left and right are equal, an odd column is left on the right
+    pub fn center_horizontally(&mut self) {
+        if let Some(region) = self.content_bounds() {
+            let x = (self.width - region.width) / 2;
+            self.shift(x as isize - region.x as isize, 0, NO_WRAP);
+        }
+    }
+
+    /// Move the dots so the empty rows above and below are equal, an odd row is left at the bottom
+    pub fn center_vertically(&mut self) {
+        if let Some(region) = self.content_bounds() {
+            let y = (self.height - region.height) / 2;
+            self.shift(0, y as isize - region.y as isize, NO_WRAP);
+        }
+    }
+
+    /// Move the dots up or down so the lowest set dot is on row `baseline`
+    ///
+    /// Dots moved past the top or bottom are removed
+    pub fn align_to_baseline(&mut self, baseline: usize) {
+        if let Some(region) = self.content_bounds() {
+            let baseline = baseline.min(self.height - 1);
+            let bottom = region.y + region.height - 1;
+            self.shift(0, baseline as isize - bottom as isize, NO_WRAP);
+        }
+    }
+
+    /// Region covering the whole glyph
+    pub fn bounds(&self) -> Region {
+        Region::new(0, 0, self.width, self.height)
+    }
+
+    /// New glyph containing the dots and guides inside `region`
+    pub fn copy_region(&self, region: Region) -> Glyph {
+        let mut output = Glyph::new(region.width, region.height);
+        output.guides = vec![vec![false; region.width * region.height]; self.guides.len()];
+        for y in 0..region.height {
+            for x in 0..region.width {
+                let (src_x, src_y) = (region.x + x, region.y + y);
+                if src_x < self.width && src_y < self.height {
+                    let src = src_x + src_y * self.width;
+                    output.dots[x + y * region.width] = self.dots[src];
+                    for (output, guides) in output.guides.iter_mut().zip(&self.guides) {
+                        output[x + y * region.width] = guides[src];
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    /// Unset dots and guides inside `region`
+    pub fn clear_region(&mut self, region: Region) {
+        let mut empty = self.copy_region(region);
+        empty.clear();
+        empty.clear_guides();
+        self.paste(&empty, region.x as isize, region.y as isize);
+    }
+
+    /// Replace the cells under `other` with its dots and guides, with its top left at `x`,`y`
+    ///
+    /// Any part of `other` outside this glyph is ignored
+    pub fn paste(&mut self, other: &Glyph, x: isize, y: isize) {
+        for src_y in 0..other.height {
+            for src_x in 0..other.width {
+                let dest_x = x + src_x as isize;
+                let dest_y = y + src_y as isize;
+                if (0..self.width as isize).contains(&dest_x)
+                    && (0..self.height as isize).contains(&dest_y)
+                {
+                    let dest = dest_x as usize + dest_y as usize * self.width;
+                    let src = src_x + src_y * other.width;
+                    self.dots[dest] = other.dots[src];
+                    for (guides, other) in self.guides.iter_mut().zip(&other.guides) {
+                        guides[dest] = other[src];
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run `edit` on only the cells inside `region`
+    ///
+    /// e.g. `glyph.edit_region(region, Glyph::flip_h)` flips just that area
+    pub fn edit_region<F: FnOnce(&mut Glyph)>(&mut self, region: Region, edit: F) {
+        let mut part = self.copy_region(region);
+        edit(&mut part);
+        self.paste(&part, region.x as isize, region.y as isize);
+    }
+}
+
+/// Copy `cells` into a grid of `new_size`, moved by `offset`, cropping or padding as needed
+pub(crate) fn resizer(
+    old_size: (usize, usize),
+    new_size: (usize, usize),
+    offset: (isize, isize),
+    cells: &[bool],
+) -> Vec<bool> {
+    let mut output = vec![false; new_size.0 * new_size.1];
+    for y in 0..old_size.1 {
+        for x in 0..old_size.0 {
+            let new_x = x as isize + offset.0;
+            let new_y = y as isize + offset.1;
+            if (0..new_size.0 as isize).contains(&new_x)
+                && (0..new_size.1 as isize).contains(&new_y)
+            {
+                output[new_x as usize + new_y as usize * new_size.0] = cells[x + y * old_size.0];
+            }
+        }
+    }
+    output
+}
+
+/// Move `cells` in a grid of `size` by `offset`, wrapping around the edges or removing cells past them
+fn shifter(size: (usize, usize), offset: (isize, isize), wrap: bool, cells: &[bool]) -> Vec<bool> {
+    if !wrap {
+        return resizer(size, size, offset, cells);
+    }
+    let mut output = vec![false; cells.len()];
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let new_x = (x as isize + offset.0).rem_euclid(size.0 as isize) as usize;
+            let new_y = (y as isize + offset.1).rem_euclid(size.1 as isize) as usize;
+            output[new_x + new_y * size.0] = cells[x + y * size.0];
+        }
+    }
+    output
+}
+
+fn horz_swapper(width: usize, dots: &[bool]) -> Vec<bool> {
+    let mut output = dots.to_vec();
+    for row in output.chunks_mut(width) {
+        row.reverse();
+    }
+    output
+}
+
+fn vert_swapper(width: usize, height: usize, dots: &[bool]) -> Vec<bool> {
+    let mut output = dots.to_vec();
+    let (top, bottom) = output.split_at_mut(height / 2 * width);
+    let bottom_start = bottom.len() - top.len();
+    for (top_row, bottom_row) in top
+        .chunks_mut(width)
+        .zip(bottom[bottom_start..].chunks_mut(width).rev())
+    {
+        top_row.swap_with_slice(bottom_row);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glyph from rows of `#` (set) and `.` (unset)
+    fn glyph(rows: &[&str]) -> Glyph {
+        let mut glyph = Glyph::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, chr) in row.chars().enumerate() {
+                glyph.dots[x + y * glyph.width] = chr == '#';
+            }
+        }
+        glyph
+    }
+
+    fn rows(cells: &[bool], width: usize) -> Vec<String> {
+        cells
+            .chunks(width)
+            .map(|row| row.iter().map(|&set| if set { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn flips() {
+        let mut odd = glyph(&["##.", "...", "..#"]);
+        odd.flip_h();
+        assert_eq!(rows(&odd.dots, 3), [".##", "...", "#.."]);
+        odd.flip_v();
+        assert_eq!(rows(&odd.dots, 3), ["#..", "...", ".##"]);
+
+        let mut even = glyph(&["#.", "..", ".#", "##"]);
+        even.flip_v();
+        assert_eq!(rows(&even.dots, 2), ["##", ".#", "..", "#."]);
+    }
+
+    #[test]
+    fn anchor_offsets() {
+        assert_eq!(Anchor::TopLeft.offset((2, 2), (4, 4)), (0, 0));
+        assert_eq!(Anchor::Center.offset((2, 2), (4, 4)), (1, 1));
+        assert_eq!(Anchor::BottomRight.offset((2, 2), (4, 4)), (2, 2));
+        assert_eq!(Anchor::BottomLeft.offset((4, 4), (3, 2)), (0, -2));
+        assert_eq!(Anchor::Top.offset((3, 3), (2, 3)), (0, 0));
+    }
+
+    #[test]
+    fn resize_keeps_content_at_anchor() {
+        let mut grown = glyph(&["#.", ".#"]);
+        grown.resize(3, 3, Anchor::TopLeft);
+        assert_eq!(rows(&grown.dots, 3), ["#..", ".#.", "..."]);
+
+        let mut grown = glyph(&["#.", ".#"]);
+        grown.resize(3, 3, Anchor::BottomRight);
+        assert_eq!(rows(&grown.dots, 3), ["...", ".#.", "..#"]);
+
+        let mut shrunk = glyph(&["#..", ".#.", "..#"]);
+        shrunk.resize(2, 2, Anchor::BottomLeft);
+        assert_eq!(rows(&shrunk.dots, 2), [".#", ".."]);
+        assert_eq!(shrunk.guides[0].len(), 4);
+    }
+
+    #[test]
+    fn paste_str_needs_every_dot() {
+        let source = glyph(&["#.", ".#"]);
+        let mut target = Glyph::new(2, 2);
+        target
+            .paste_str(source.copy_str().trim_end_matches(','))
+            .unwrap();
+        assert_eq!(target, source);
+        assert!(target.paste_str("true,false").is_err());
+    }
+
+    #[test]
+    fn copy_and_paste_region() {
+        let source = glyph(&["#..", ".#.", "..#"]);
+        let part = source.copy_region(Region::new(1, 1, 2, 2));
+        assert_eq!(rows(&part.dots, 2), ["#.", ".#"]);
+
+        let mut target = Glyph::new(3, 3);
+        target.paste(&part, 2, -1);
+        assert_eq!(rows(&target.dots, 3), ["...", "...", "..."]);
+        target.paste(&part, 2, 0);
+        assert_eq!(rows(&target.dots, 3), ["..#", "...", "..."]);
+
+        let mut cleared = source.clone();
+        cleared.clear_region(Region::new(0, 0, 2, 2));
+        assert_eq!(rows(&cleared.dots, 3), ["...", "...", "..#"]);
+    }
+
+    #[test]
+    fn region_clamps_to_glyph() {
+        let region = Region::from_corners((3, 1), (1, 0));
+        assert_eq!(region, Region::new(1, 0, 3, 2));
+        assert_eq!(region.clamp_to((2, 2)), Some(Region::new(1, 0, 1, 2)));
+        assert_eq!(region.clamp_to((1, 1)), None);
+    }
+
+    #[test]
+    fn rotate_and_transpose() {
+        let original = glyph(&["#..", "##."]);
+
+        let mut rotated = original.clone();
+        rotated.rotate_cw();
+        assert_eq!(rotated.size(), (2, 3));
+        assert_eq!(rows(&rotated.dots, 2), ["##", "#.", ".."]);
+        rotated.rotate_ccw();
+        assert_eq!(rotated, original);
+
+        let mut transposed = original.clone();
+        transposed.set_guides(0, &[(2, 0)], true);
+        transposed.transpose();
+        assert_eq!(rows(&transposed.dots, 2), ["##", ".#", ".."]);
+        assert_eq!(rows(&transposed.guides[0], 2), ["..", "..", "#."]);
+    }
+
+    #[test]
+    fn shear_moves_dots_and_guides() {
+        let mut steep = glyph(&["#..", "#..", "#.."]);
+        steep.set_guides(0, &[(0, 0), (0, 1), (0, 2)], true);
+        steep.shear(1);
+        assert_eq!(rows(&steep.dots, 3), ["..#", ".#.", "#.."]);
+        assert_eq!(steep.guides[0], steep.dots);
+
+        let mut shallow = glyph(&["#.", "#.", "#."]);
+        shallow.shear(2);
+        assert_eq!(rows(&shallow.dots, 2), [".#", "#.", "#."]);
+    }
+
+    #[test]
+    fn shift_wraps_or_removes() {
+        let mut wrapped = glyph(&["..#", "..."]);
+        wrapped.shift(1, 1, ShiftOptions::default());
+        assert_eq!(rows(&wrapped.dots, 3), ["...", "#.."]);
+
+        let mut removed = glyph(&["..#", "..."]);
+        removed.shift(
+            1,
+            1,
+            ShiftOptions {
+                wrap: false,
+                guides: false,
+            },
+        );
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn shift_moves_guides_only_if_set() {
+        let mut glyph = glyph(&["#.", ".."]);
+        glyph.set_guides(0, &[(0, 0)], true);
+        glyph.shift(1, 0, ShiftOptions::default());
+        assert_eq!(rows(&glyph.guides[0], 2), ["#.", ".."]);
+        glyph.shift(
+            1,
+            0,
+            ShiftOptions {
+                wrap: true,
+                guides: true,
+            },
+        );
+        assert_eq!(rows(&glyph.guides[0], 2), [".#", ".."]);
+        assert_eq!(rows(&glyph.dots, 2), ["#.", ".."]);
+    }
+
+    #[test]
+    fn trim_and_center() {
+        let mut trimmed = glyph(&["....", ".#..", ".##.", "...."]);
+        trimmed.trim();
+        assert_eq!(trimmed.size(), (2, 2));
+        assert_eq!(rows(&trimmed.dots, 2), ["#.", "##"]);
+
+        let mut empty = Glyph::new(3, 3);
+        empty.trim();
+        assert_eq!(empty.size(), (3, 3));
+
+        let mut centered = glyph(&["#....", "#...."]);
+        centered.center_horizontally();
+        assert_eq!(rows(&centered.dots, 5), ["..#..", "..#.."]);
+
+        let mut centered = glyph(&["#", ".", ".", "."]);
+        centered.center_vertically();
+        assert_eq!(rows(&centered.dots, 1), [".", "#", ".", "."]);
+
+        let mut aligned = glyph(&["#", ".", "."]);
+        aligned.align_to_baseline(1);
+        assert_eq!(rows(&aligned.dots, 1), [".", "#", "."]);
+    }
+
+    #[test]
+    fn single_guide_list_is_one_layer() {
+        let glyph: Glyph = serde_json::from_str(
+            r#"{"width":2,"height":1,"dots":[true,false],"guides":[false,true]}"#,
+        )
+        .unwrap();
+        assert_eq!(glyph.guides, vec![vec![false, true]]);
+    }
+}
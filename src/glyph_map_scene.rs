@@ -0,0 +1,197 @@
+use crate::preview::Preview;
+use crate::{SceneName, SceneResult, HEIGHT, WIDTH};
+use fontpad::font::Font;
+use pixels_graphics_lib::prelude::SceneUpdateResult::{Nothing, Pop};
+use pixels_graphics_lib::prelude::*;
+use pixels_graphics_lib::ui::styles::UiStyle;
+
+const EMPTY_COLOR: Color = Color::new(140, 40, 40, 255);
+const BORDER_COLOR: Color = DARK_GRAY;
+const CURRENT_COLOR: Color = WHITE;
+const HOVER_COLOR: Color = LIGHT_GRAY;
+const TOP: usize = 16;
+const BOTTOM: usize = 14;
+const PADDING: usize = 3;
+
+struct Thumbnail {
+    chr: char,
+    image: Image,
+    empty: bool,
+}
+
+/// Shows every glyph in the font at 1x, click on one to edit it
+pub struct GlyphMapScene {
+    bg_color: Color,
+    result: SceneUpdateResult<SceneResult, SceneName>,
+    thumbnails: Vec<Thumbnail>,
+    current: char,
+    cell_size: (usize, usize),
+    columns: usize,
+    visible_rows: usize,
+    first_row: usize,
+    empty_count: usize,
+}
+
+impl GlyphMapScene {
+    pub fn new(font: &Font, current: char, style: &UiStyle) -> Box<Self> {
+        let thumbnails: Vec<Thumbnail> = font
+            .glyphs
+            .iter()
+            .map(|(&chr, glyph)| Thumbnail {
+                chr,
+                image: Preview::create_image((glyph.width, glyph.height), &glyph.dots),
+                empty: glyph.is_empty(),
+            })
+            .collect();
+        let max_width = font.glyphs.values().map(|g| g.width).max().unwrap_or(0);
+        let max_height = font.glyphs.values().map(|g| g.height).max().unwrap_or(0);
+        let cell_size = (
+            max_width.max(PixelFont::Standard6x7.size().0) + PADDING * 2,
+            max_height.max(PixelFont::Standard6x7.size().1) + PADDING * 2,
+        );
+        let columns = ((WIDTH - 8) / cell_size.0).max(1);
+        let visible_rows = ((HEIGHT - TOP - BOTTOM) / cell_size.1).max(1);
+        let empty_count = thumbnails.iter().filter(|t| t.empty).count();
+        let mut scene = GlyphMapScene {
+            bg_color: style.background,
+            result: Nothing,
+            thumbnails,
+            current,
+            cell_size,
+            columns,
+            visible_rows,
+            first_row: 0,
+            empty_count,
+        };
+        if let Some(i) = scene.thumbnails.iter().position(|t| t.chr == current) {
+            scene.scroll_to(i / columns);
+        }
+        Box::new(scene)
+    }
+}
+
+impl GlyphMapScene {
+    fn row_count(&self) -> usize {
+        self.thumbnails.len().div_ceil(self.columns)
+    }
+
+    fn scroll_to(&mut self, row: usize) {
+        let max = self.row_count().saturating_sub(self.visible_rows);
+        self.first_row = row.min(max);
+    }
+
+    fn cell_rect(&self, idx: usize) -> Option<Rect> {
+        let row = idx / self.columns;
+        if row < self.first_row || row >= self.first_row + self.visible_rows {
+            return None;
+        }
+        let col = idx % self.columns;
+        let row = row - self.first_row;
+        Some(Rect::new_with_size(
+            (4 + col * self.cell_size.0, TOP + row * self.cell_size.1),
+            self.cell_size.0 - 1,
+            self.cell_size.1 - 1,
+        ))
+    }
+
+    fn thumbnail_at(&self, xy: Coord) -> Option<usize> {
+        (0..self.thumbnails.len())
+            .find(|&i| self.cell_rect(i).map(|r| r.contains(xy)).unwrap_or(false))
+    }
+}
+
+impl Scene<SceneResult, SceneName> for GlyphMapScene {
+    fn render(&self, graphics: &mut Graphics, mouse: &MouseData, _: &FxHashSet<KeyCode>) {
+        graphics.clear(self.bg_color);
+        graphics.draw_text(
+            &format!(
+                "{} glyphs, {} empty",
+                self.thumbnails.len(),
+                self.empty_count
+            ),
+            TextPos::px(coord!(4, 4)),
+            (WHITE, PixelFont::Standard6x7),
+        );
+        let hovered = self.thumbnail_at(mouse.xy);
+        for (i, thumbnail) in self.thumbnails.iter().enumerate() {
+            if let Some(rect) = self.cell_rect(i) {
+                let color = if thumbnail.chr == self.current {
+                    CURRENT_COLOR
+                } else if hovered == Some(i) {
+                    HOVER_COLOR
+                } else if thumbnail.empty {
+                    EMPTY_COLOR
+                } else {
+                    BORDER_COLOR
+                };
+                graphics.draw_rect(rect.clone(), stroke(color));
+                if thumbnail.empty {
+                    graphics.draw_text(
+                        &thumbnail.chr.to_string(),
+                        TextPos::px(rect.center() + (0, 1)),
+                        (EMPTY_COLOR, PixelFont::Standard6x7, Positioning::Center),
+                    );
+                } else {
+                    let offset =
+                        rect.center() - (thumbnail.image.width() / 2, thumbnail.image.height() / 2);
+                    graphics.draw_image(offset, &thumbnail.image);
+                }
+            }
+        }
+        if let Some(thumbnail) = hovered.map(|i| &self.thumbnails[i]) {
+            graphics.draw_text(
+                &format!(
+                    "'{}' U+{:04X}{}",
+                    thumbnail.chr,
+                    thumbnail.chr as u32,
+                    if thumbnail.empty { " (empty)" } else { "" }
+                ),
+                TextPos::px(coord!(4, HEIGHT - BOTTOM + 4)),
+                (WHITE, PixelFont::Standard6x7),
+            );
+        }
+    }
+
+    fn on_key_up(&mut self, key: KeyCode, _: &MouseData, _: &FxHashSet<KeyCode>) {
+        match key {
+            KeyCode::Escape => self.result = Pop(None),
+            KeyCode::PageUp => self.scroll_to(self.first_row.saturating_sub(self.visible_rows)),
+            KeyCode::PageDown => self.scroll_to(self.first_row + self.visible_rows),
+            _ => {}
+        }
+    }
+
+    fn on_mouse_click(
+        &mut self,
+        down_at: Coord,
+        mouse: &MouseData,
+        mouse_button: MouseButton,
+        _: &FxHashSet<KeyCode>,
+    ) {
+        if mouse_button == MouseButton::Left {
+            if let Some(i) = self.thumbnail_at(down_at) {
+                if self.thumbnail_at(mouse.xy) == Some(i) {
+                    self.result = Pop(Some(SceneResult::SelectGlyph(self.thumbnails[i].chr)));
+                }
+            }
+        }
+    }
+
+    fn on_scroll(&mut self, _: &MouseData, _: isize, y_diff: isize, _: &FxHashSet<KeyCode>) {
+        if y_diff < 0 {
+            self.scroll_to(self.first_row.saturating_sub(1));
+        } else if y_diff > 0 {
+            self.scroll_to(self.first_row + 1);
+        }
+    }
+
+    fn update(
+        &mut self,
+        _: &Timing,
+        _: &MouseData,
+        _: &FxHashSet<KeyCode>,
+        _: &Window,
+    ) -> SceneUpdateResult<SceneResult, SceneName> {
+        self.result.clone()
+    }
+}
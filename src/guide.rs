@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Layer created for fonts from before guide layers
+pub const DEFAULT_LAYER_NAME: &str = "Guides";
+/// Colour of the default layer, matches the guides from before layers
+pub const DEFAULT_LAYER_COLOR: [u8; 3] = [110, 110, 110];
+
+/// Named set of guide marks, each glyph has its own marks unless the layer is shared by the whole font
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuideLayer {
+    pub name: String,
+    /// RGB
+    pub color: [u8; 3],
+    pub visible: bool,
+    /// Every glyph shows the same marks, stored in `cells`
+    #[serde(default)]
+    pub shared: bool,
+    /// Marks for a shared layer, in a grid the default size of the font
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cells: Vec<bool>,
+}
+
+impl GuideLayer {
+    pub fn new(name: &str, color: [u8; 3]) -> GuideLayer {
+        GuideLayer {
+            name: name.to_string(),
+            color,
+            visible: true,
+            shared: false,
+            cells: vec![],
+        }
+    }
+}
+
+/// Layers for new fonts and fonts from before guide layers
+pub fn default_layers() -> Vec<GuideLayer> {
+    vec![GuideLayer::new(DEFAULT_LAYER_NAME, DEFAULT_LAYER_COLOR)]
+}
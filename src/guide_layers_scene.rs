@@ -0,0 +1,284 @@
+use crate::pad_view::layer_color;
+use crate::{SceneName, SceneResult, HEIGHT};
+use fontpad::guide::GuideLayer;
+use pixels_graphics_lib::prelude::SceneUpdateResult::{Nothing, Pop};
+use pixels_graphics_lib::prelude::*;
+use pixels_graphics_lib::ui::prelude::*;
+use pixels_graphics_lib::ui::styles::UiStyle;
+
+const MAX_LAYERS: usize = 8;
+const TOP: usize = 18;
+const ROW_HEIGHT: usize = 16;
+const ROW_WIDTH: usize = 352;
+const SWATCH_SIZE: usize = 12;
+const NAME_LENGTH: usize = 20;
+const VISIBLE_X: isize = 220;
+const SHARED_X: isize = 280;
+const CURRENT_COLOR: Color = WHITE;
+const HOVER_COLOR: Color = LIGHT_GRAY;
+const BORDER_COLOR: Color = DARK_GRAY;
+/// Colours a swatch cycles through when clicked, new layers take the next unused one
+const PALETTE: [[u8; 3]; 8] = [
+    [110, 110, 110],
+    [200, 60, 60],
+    [60, 170, 60],
+    [70, 110, 220],
+    [210, 190, 40],
+    [190, 70, 190],
+    [40, 180, 190],
+    [220, 130, 40],
+];
+
+/// Add, remove, rename and recolour guide layers, and choose which are shown and shared by the font
+///
+/// Layers are edited as a copy and only returned when Done is clicked
+pub struct GuideLayersScene {
+    bg_color: Color,
+    result: SceneUpdateResult<SceneResult, SceneName>,
+    /// Each layer with the index it had when the scene opened, None for new layers
+    layers: Vec<(Option<usize>, GuideLayer)>,
+    /// Layer shift painting changes
+    active: usize,
+    name: TextField,
+    add: Button,
+    remove: Button,
+    done: Button,
+}
+
+impl GuideLayersScene {
+    pub fn new(layers: &[GuideLayer], active: usize, style: &UiStyle) -> Box<Self> {
+        let active = active.min(layers.len().saturating_sub(1));
+        let mut scene = GuideLayersScene {
+            bg_color: style.background,
+            result: Nothing,
+            layers: layers
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(i, layer)| (Some(i), layer))
+                .collect(),
+            active,
+            name: TextField::new(
+                coord!(44, 160),
+                NAME_LENGTH,
+                PixelFont::Standard6x7,
+                (None, None),
+                "",
+                &[
+                    TextFilter::Letters,
+                    TextFilter::Numbers,
+                    TextFilter::Whitespace,
+                ],
+                &style.text_field,
+            ),
+            add: Button::new(coord!(4, 184), "Add", Some(50), &style.button),
+            remove: Button::new(coord!(60, 184), "Remove", Some(50), &style.button),
+            done: Button::new(coord!(4, HEIGHT - 20), "Done", Some(50), &style.button),
+        };
+        scene.select(active);
+        Box::new(scene)
+    }
+}
+
+impl GuideLayersScene {
+    fn row_rect(&self, idx: usize) -> Rect {
+        Rect::new_with_size((4, TOP + idx * ROW_HEIGHT), ROW_WIDTH, ROW_HEIGHT - 2)
+    }
+
+    fn row_at(&self, xy: Coord) -> Option<usize> {
+        (0..self.layers.len()).find(|&i| self.row_rect(i).contains(xy))
+    }
+
+    fn swatch_rect(&self, idx: usize) -> Rect {
+        Rect::new_with_size(
+            self.row_rect(idx).top_left() + (2, 1),
+            SWATCH_SIZE,
+            SWATCH_SIZE - 1,
+        )
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.active = idx;
+        self.name.set_content(&self.layers[idx].1.name);
+        self.update_buttons();
+    }
+
+    fn update_buttons(&mut self) {
+        self.add.set_state(if self.layers.len() < MAX_LAYERS {
+            ViewState::Normal
+        } else {
+            ViewState::Disabled
+        });
+        self.remove.set_state(if self.layers.len() > 1 {
+            ViewState::Normal
+        } else {
+            ViewState::Disabled
+        });
+    }
+
+    fn add_layer(&mut self) {
+        if self.layers.len() >= MAX_LAYERS {
+            return;
+        }
+        let color = PALETTE
+            .iter()
+            .find(|color| !self.layers.iter().any(|(_, layer)| layer.color == **color))
+            .copied()
+            .unwrap_or(PALETTE[0]);
+        let name = format!("Layer {}", self.layers.len() + 1);
+        self.layers.push((None, GuideLayer::new(&name, color)));
+        self.select(self.layers.len() - 1);
+    }
+
+    fn remove_layer(&mut self) {
+        if self.layers.len() <= 1 {
+            return;
+        }
+        self.layers.remove(self.active);
+        self.select(self.active.min(self.layers.len() - 1));
+    }
+
+    /// Swap the colour of `idx` for the next one in [PALETTE]
+    fn next_color(&mut self, idx: usize) {
+        let layer = &mut self.layers[idx].1;
+        let next = PALETTE
+            .iter()
+            .position(|color| *color == layer.color)
+            .map(|i| (i + 1) % PALETTE.len())
+            .unwrap_or(0);
+        layer.color = PALETTE[next];
+    }
+
+    /// Return the layers to the pad, blank names are replaced
+    fn finish(&mut self) {
+        let mut layers = self.layers.clone();
+        for (i, (_, layer)) in layers.iter_mut().enumerate() {
+            if layer.name.trim().is_empty() {
+                layer.name = format!("Layer {}", i + 1);
+            }
+        }
+        self.result = Pop(Some(SceneResult::GuideLayers(layers, self.active)));
+    }
+}
+
+impl Scene<SceneResult, SceneName> for GuideLayersScene {
+    fn render(&self, graphics: &mut Graphics, mouse: &MouseData, _: &FxHashSet<KeyCode>) {
+        graphics.clear(self.bg_color);
+        graphics.draw_text(
+            "Guide layers",
+            TextPos::px(coord!(4, 4)),
+            (WHITE, PixelFont::Standard6x7),
+        );
+        let hovered = self.row_at(mouse.xy);
+        for (i, (_, layer)) in self.layers.iter().enumerate() {
+            let rect = self.row_rect(i);
+            let color = if i == self.active {
+                CURRENT_COLOR
+            } else if hovered == Some(i) {
+                HOVER_COLOR
+            } else {
+                BORDER_COLOR
+            };
+            graphics.draw_rect(rect.clone(), stroke(color));
+            graphics.draw_rect(self.swatch_rect(i), fill(layer_color(layer)));
+            let text_y = rect.top() + 4;
+            graphics.draw_text(
+                &layer.name,
+                TextPos::px(coord!(rect.left() + SWATCH_SIZE as isize + 6, text_y)),
+                (WHITE, PixelFont::Standard6x7),
+            );
+            graphics.draw_text(
+                if layer.visible { "Shown" } else { "Hidden" },
+                TextPos::px(coord!(VISIBLE_X, text_y)),
+                (WHITE, PixelFont::Standard6x7),
+            );
+            graphics.draw_text(
+                if layer.shared { "Font" } else { "Glyph" },
+                TextPos::px(coord!(SHARED_X, text_y)),
+                (WHITE, PixelFont::Standard6x7),
+            );
+        }
+        graphics.draw_text(
+            "Name",
+            TextPos::px(coord!(4, 163)),
+            (WHITE, PixelFont::Standard6x7),
+        );
+        self.name.render(graphics, mouse);
+        self.add.render(graphics, mouse);
+        self.remove.render(graphics, mouse);
+        self.done.render(graphics, mouse);
+        graphics.draw_text(
+            "Click a colour to change it, Shown/Hidden to toggle drawing\nand Font/Glyph to share the marks with every glyph",
+            TextPos::px(coord!(4, 210)),
+            (LIGHT_GRAY, PixelFont::Standard4x5),
+        );
+    }
+
+    fn on_key_up(&mut self, key: KeyCode, _: &MouseData, held: &FxHashSet<KeyCode>) {
+        if self.name.is_focused() {
+            match key {
+                KeyCode::Escape | KeyCode::Enter => self.name.unfocus(),
+                _ => {
+                    self.name.on_key_press(key, held);
+                    self.layers[self.active].1.name = self.name.content().to_string();
+                }
+            }
+            return;
+        }
+        match key {
+            KeyCode::Escape => self.result = Pop(None),
+            KeyCode::Enter => self.finish(),
+            KeyCode::ArrowUp => self.select(self.active.saturating_sub(1)),
+            KeyCode::ArrowDown => self.select((self.active + 1).min(self.layers.len() - 1)),
+            _ => {}
+        }
+    }
+
+    fn on_mouse_click(
+        &mut self,
+        down_at: Coord,
+        mouse: &MouseData,
+        mouse_button: MouseButton,
+        _: &FxHashSet<KeyCode>,
+    ) {
+        if mouse_button != MouseButton::Left {
+            return;
+        }
+        self.name.on_mouse_click(down_at, mouse.xy);
+        if self.add.on_mouse_click(down_at, mouse.xy) {
+            self.add_layer();
+        }
+        if self.remove.on_mouse_click(down_at, mouse.xy) {
+            self.remove_layer();
+        }
+        if self.done.on_mouse_click(down_at, mouse.xy) {
+            self.finish();
+        }
+        let Some(i) = self
+            .row_at(down_at)
+            .filter(|&i| self.row_at(mouse.xy) == Some(i))
+        else {
+            return;
+        };
+        let x = down_at.x;
+        if self.swatch_rect(i).contains(down_at) {
+            self.next_color(i);
+        } else if (VISIBLE_X..SHARED_X).contains(&x) {
+            self.layers[i].1.visible = !self.layers[i].1.visible;
+        } else if x >= SHARED_X {
+            self.layers[i].1.shared = !self.layers[i].1.shared;
+        }
+        self.select(i);
+    }
+
+    fn update(
+        &mut self,
+        timing: &Timing,
+        _: &MouseData,
+        _: &FxHashSet<KeyCode>,
+        _: &Window,
+    ) -> SceneUpdateResult<SceneResult, SceneName> {
+        self.name.update(timing);
+        self.result.clone()
+    }
+}
@@ -0,0 +1,108 @@
+/// Undo/redo stacks of snapshots, each holding at most `limit`
+#[derive(Debug)]
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    limit: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(limit: usize) -> History<T> {
+        History {
+            undo: vec![],
+            redo: vec![],
+            limit,
+        }
+    }
+}
+
+impl<T> History<T> {
+    /// Store `before`, the state prior to an edit
+    ///
+    /// Clears the redo stack and drops the oldest entry if over the limit
+    pub fn record(&mut self, before: T) {
+        self.redo.clear();
+        push(&mut self.undo, before, self.limit);
+    }
+
+    /// Returns the state to restore, `current` is kept for redo
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop()?;
+        push(&mut self.redo, current, self.limit);
+        Some(state)
+    }
+
+    /// Returns the state to restore, `current` is kept for undo
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        push(&mut self.undo, current, self.limit);
+        Some(state)
+    }
+
+    /// State the next undo will restore
+    pub fn peek_undo(&self) -> Option<&T> {
+        self.undo.last()
+    }
+
+    /// State the next redo will restore
+    pub fn peek_redo(&self) -> Option<&T> {
+        self.redo.last()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Add `state` to `stack`, dropping the oldest entry if over `limit`
+fn push<T>(stack: &mut Vec<T>, state: T, limit: usize) {
+    stack.push(state);
+    if stack.len() > limit {
+        stack.remove(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new(10);
+        history.record(1);
+        history.record(2);
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.peek_redo(), Some(&3));
+        assert_eq!(history.peek_undo(), Some(&1));
+        history.record(5);
+        assert_eq!(history.peek_redo(), None);
+    }
+
+    #[test]
+    fn drops_oldest_over_limit() {
+        let mut history = History::new(2);
+        history.record(1);
+        history.record(2);
+        history.record(3);
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+    }
+
+    #[test]
+    fn redo_is_limited() {
+        let mut history = History {
+            undo: vec![1, 2, 3],
+            redo: vec![],
+            limit: 2,
+        };
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.redo, [3, 2]);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod bdf;
+pub mod font;
+pub mod format;
+pub mod glyph;
+pub mod guide;
+pub mod packed;
+pub mod project;
+pub mod rust_source;
+pub mod shape;
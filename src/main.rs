@@ -1,39 +1,131 @@
+mod anchor_picker;
+mod cli;
+mod glyph_map_scene;
+mod guide_layers_scene;
+mod history;
 mod pad_scene;
 mod pad_view;
 mod preview;
+mod recovery;
 
+use crate::glyph_map_scene::GlyphMapScene;
+use crate::guide_layers_scene::GuideLayersScene;
 use crate::pad_scene::PadScene;
 use anyhow::Result;
+use fontpad::font::Font;
+use fontpad::guide::GuideLayer;
+use fontpad::packed::PackedFormat;
+use fontpad::project::{LegacyGlyph, LEGACY_CHAR};
+use pixels_graphics_lib::dialogs::load_file_dialog::LoadFileDialog;
+use pixels_graphics_lib::dialogs::save_file_dialog::SaveFileDialog;
 use pixels_graphics_lib::prelude::*;
 use serde::{Deserialize, Serialize};
 
+pub const WIDTH: usize = 360;
+pub const HEIGHT: usize = 282;
+pub const TITLE: &str = "Font Pad";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SettingsFile")]
 struct Settings {
-    pub width: usize,
-    pub height: usize,
-    pub dots: Vec<bool>,
-    pub guides: Vec<bool>,
+    pub font: Font,
+    /// Character currently being edited
+    pub current: char,
+    /// Layout used when copying or exporting packed arrays
+    pub packed: PackedFormat,
+    /// Project file the font was last opened from or saved to
+    pub project_path: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            font: Font::new(5, 5),
+            current: 'A',
+            packed: PackedFormat::default(),
+            project_path: None,
+        }
+    }
+}
+
+/// Every layout settings have been saved in
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SettingsFile {
+    Current {
+        font: Font,
+        current: char,
+        #[serde(default)]
+        packed: PackedFormat,
+        #[serde(default)]
+        project_path: Option<String>,
+    },
+    /// Before fonts had more than one glyph
+    Legacy(LegacyGlyph),
+}
+
+impl From<SettingsFile> for Settings {
+    fn from(value: SettingsFile) -> Self {
+        match value {
+            SettingsFile::Current {
+                font,
+                current,
+                packed,
+                project_path,
+            } => Settings {
+                font,
+                current,
+                packed,
+                project_path,
+            },
+            SettingsFile::Legacy(glyph) => Settings {
+                font: glyph.into_font(),
+                current: LEGACY_CHAR,
+                ..Settings::default()
+            },
+        }
+    }
 }
 
 fn settings() -> AppPrefs<Settings> {
-    AppPrefs::new("app", "emmabritton", "fontpad", || Settings {
-        width: 5,
-        height: 5,
-        dots: vec![false; 25],
-        guides: vec![false; 25],
-    })
-    .expect("Unable to create prefs file")
+    AppPrefs::new("app", "emmabritton", "fontpad", Settings::default)
+        .expect("Unable to create prefs file")
 }
 
 fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
     let window_prefs = WindowPreferences::new("com", "emmabritton", "fontpad", 1)?;
-    let options = Options::default();
-    let switcher: SceneSwitcher<SceneResult, SceneName> = |_, _, _| {};
+    let mut options = Options::default();
+    options.style.dialog.bounds = Rect::new_with_size(
+        (
+            (WIDTH - MIN_FILE_DIALOG_SIZE.0) / 2,
+            (HEIGHT - MIN_FILE_DIALOG_SIZE.1) / 2,
+        ),
+        MIN_FILE_DIALOG_SIZE.0,
+        MIN_FILE_DIALOG_SIZE.1,
+    );
+    let switcher: SceneSwitcher<SceneResult, SceneName> = |style, scenes, name| match name {
+        SceneName::GlyphMap(font, current) => {
+            scenes.push(GlyphMapScene::new(&font, current, style))
+        }
+        SceneName::GuideLayers(layers, active) => {
+            scenes.push(GuideLayersScene::new(&layers, active, style))
+        }
+        SceneName::LoadFile(ext) => {
+            scenes.push(LoadFileDialog::new(Some(&ext), WIDTH, HEIGHT, style))
+        }
+        SceneName::SaveFile(ext, path) => {
+            scenes.push(SaveFileDialog::new(path, Some(&ext), WIDTH, HEIGHT, style))
+        }
+    };
     let first_scene = PadScene::new(&options.style);
     run_scenes(
-        300,
-        250,
-        "Font Pad",
+        WIDTH,
+        HEIGHT,
+        TITLE,
         Some(window_prefs),
         switcher,
         first_scene,
@@ -44,7 +136,31 @@ fn main() -> Result<()> {
 }
 
 #[derive(Clone, Debug, PartialEq)]
-enum SceneName {}
+enum SceneName {
+    GlyphMap(Font, char),
+    /// Current layers, active layer
+    GuideLayers(Vec<GuideLayer>, usize),
+    /// Allowed extension
+    LoadFile(String),
+    /// Expected extension, current file path
+    SaveFile(String, Option<String>),
+}
 
 #[derive(Clone, Debug, PartialEq)]
-enum SceneResult {}
+enum SceneResult {
+    SelectGlyph(char),
+    /// Layers paired with the index they had before editing (None if new), active layer
+    GuideLayers(Vec<(Option<usize>, GuideLayer)>, usize),
+    LoadFile(String),
+    SaveFile(String),
+}
+
+impl FileDialogResults<SceneResult> for SceneResult {
+    fn save_file_result(path: String) -> SceneResult {
+        SceneResult::SaveFile(path)
+    }
+
+    fn load_file_result(path: String) -> SceneResult {
+        SceneResult::LoadFile(path)
+    }
+}
@@ -0,0 +1,327 @@
+use crate::font::Font;
+use crate::glyph::Glyph;
+use crate::rust_source::const_name;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Words per line before wrapping arrays
+const WORDS_PER_LINE: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum WordSize {
+    #[default]
+    U8,
+    U16,
+    U32,
+}
+
+impl WordSize {
+    pub fn bits(&self) -> usize {
+        match self {
+            WordSize::U8 => 8,
+            WordSize::U16 => 16,
+            WordSize::U32 => 32,
+        }
+    }
+
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            WordSize::U8 => "u8",
+            WordSize::U16 => "u16",
+            WordSize::U32 => "u32",
+        }
+    }
+
+    pub fn c_type(&self) -> &'static str {
+        match self {
+            WordSize::U8 => "uint8_t",
+            WordSize::U16 => "uint16_t",
+            WordSize::U32 => "uint32_t",
+        }
+    }
+}
+
+/// Order pixels are read from the glyph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum PixelOrder {
+    /// Left to right, then top to bottom
+    #[default]
+    RowMajor,
+    /// Top to bottom, then left to right
+    ColumnMajor,
+    /// One word per column of a horizontal strip as tall as the word, strips go top to bottom
+    ///
+    /// With [WordSize::U8] and [BitOrder::LsbFirst] this is the SSD1306 page layout
+    Pages,
+}
+
+/// Which bit of a word holds the first pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum BitOrder {
+    #[default]
+    MsbFirst,
+    LsbFirst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Output {
+    #[default]
+    Rust,
+    C,
+    /// `CODE:HEXDIGITS` per line, like GNU Unifont .hex files
+    Hex,
+}
+
+impl Output {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Output::Rust => "rs",
+            Output::C => "h",
+            Output::Hex => "hex",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedFormat {
+    pub word_size: WordSize,
+    pub order: PixelOrder,
+    pub bit_order: BitOrder,
+    /// Start every row (or column for [PixelOrder::ColumnMajor]) on a new word
+    pub pad_rows: bool,
+    pub output: Output,
+}
+
+impl Default for PackedFormat {
+    fn default() -> Self {
+        PackedFormat {
+            word_size: WordSize::default(),
+            order: PixelOrder::default(),
+            bit_order: BitOrder::default(),
+            pad_rows: true,
+            output: Output::default(),
+        }
+    }
+}
+
+/// Pack the pixels of `glyph` into words, unused bits are 0
+pub fn pack(glyph: &Glyph, format: &PackedFormat) -> Vec<u32> {
+    let bits = format.word_size.bits();
+    let (width, height) = (glyph.width, glyph.height);
+    let dot = |x: usize, y: usize| y < height && glyph.dots[x + y * width];
+    let lines: Vec<Vec<bool>> = match format.order {
+        PixelOrder::RowMajor => (0..height)
+            .map(|y| (0..width).map(|x| dot(x, y)).collect())
+            .collect(),
+        PixelOrder::ColumnMajor => (0..width)
+            .map(|x| (0..height).map(|y| dot(x, y)).collect())
+            .collect(),
+        PixelOrder::Pages => (0..height.div_ceil(bits))
+            .flat_map(|page| {
+                (0..width).map(move |x| (0..bits).map(|bit| dot(x, page * bits + bit)).collect())
+            })
+            .collect(),
+    };
+
+    let mut words = vec![];
+    let mut pos = 0;
+    for line in lines {
+        if format.pad_rows || format.order == PixelOrder::Pages {
+            pos = 0;
+        }
+        for value in line {
+            if pos == 0 {
+                words.push(0);
+            }
+            if value {
+                let bit = match format.bit_order {
+                    BitOrder::MsbFirst => bits - 1 - pos,
+                    BitOrder::LsbFirst => pos,
+                };
+                if let Some(word) = words.last_mut() {
+                    *word |= 1 << bit;
+                }
+            }
+            pos = (pos + 1) % bits;
+        }
+    }
+    words
+}
+
+/// Packed array for a single glyph, named after `chr`
+pub fn write_glyph(chr: char, glyph: &Glyph, format: &PackedFormat) -> String {
+    let mut output = String::new();
+    let words = pack(glyph, format);
+    let name = const_name(chr);
+    let digits = format.word_size.bits() / 4;
+    match format.output {
+        Output::Rust => {
+            let _ = writeln!(output, "/// {chr:?} {}x{}", glyph.width, glyph.height);
+            let _ = writeln!(
+                output,
+                "pub const {name}: [{}; {}] = [{}];",
+                format.word_size.rust_type(),
+                words.len(),
+                word_list(&words, digits)
+            );
+        }
+        Output::C => {
+            let _ = writeln!(output, "// {chr:?} {}x{}", glyph.width, glyph.height);
+            let _ = writeln!(
+                output,
+                "static const {} {name}[{}] = {{{}}};",
+                format.word_size.c_type(),
+                words.len(),
+                word_list(&words, digits)
+            );
+        }
+        Output::Hex => {
+            let _ = write!(output, "{:04X}:", chr as u32);
+            for word in words {
+                let _ = write!(output, "{word:0digits$X}");
+            }
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Packed arrays for every glyph in `font`
+pub fn write(font: &Font, format: &PackedFormat) -> String {
+    let mut output = String::new();
+    match format.output {
+        Output::Rust => {
+            let _ = writeln!(output, "{}\n", layout_comment(format));
+        }
+        Output::C => {
+            let _ = writeln!(output, "#pragma once\n\n#include <stdint.h>\n");
+            let _ = writeln!(output, "{}\n", layout_comment(format));
+        }
+        Output::Hex => {}
+    }
+    for (&chr, glyph) in &font.glyphs {
+        output.push_str(&write_glyph(chr, glyph, format));
+        if format.output != Output::Hex {
+            output.push('\n');
+        }
+    }
+    output
+}
+
+fn layout_comment(format: &PackedFormat) -> String {
+    let order = match format.order {
+        PixelOrder::RowMajor => "row major",
+        PixelOrder::ColumnMajor => "column major",
+        PixelOrder::Pages => "vertical pages",
+    };
+    let bit_order = match format.bit_order {
+        BitOrder::MsbFirst => "MSB first",
+        BitOrder::LsbFirst => "LSB first",
+    };
+    let padding = if format.pad_rows && format.order != PixelOrder::Pages {
+        ", padded per line"
+    } else {
+        ""
+    };
+    format!(
+        "// Packed as {}, {order}, {bit_order}{padding}",
+        format.word_size.rust_type()
+    )
+}
+
+fn word_list(words: &[u32], digits: usize) -> String {
+    let values: Vec<String> = words
+        .iter()
+        .map(|word| format!("0x{word:0digits$X}"))
+        .collect();
+    if values.len() <= WORDS_PER_LINE {
+        return values.join(", ");
+    }
+    let lines: Vec<String> = values
+        .chunks(WORDS_PER_LINE)
+        .map(|line| format!("    {},", line.join(", ")))
+        .collect();
+    format!("\n{}\n", lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3x2 glyph:
+    /// ```text
+    /// #..
+    /// .##
+    /// ```
+    fn glyph() -> Glyph {
+        Glyph {
+            dots: vec![true, false, false, false, true, true],
+            ..Glyph::new(3, 2)
+        }
+    }
+
+    fn format(order: PixelOrder, bit_order: BitOrder) -> PackedFormat {
+        PackedFormat {
+            order,
+            bit_order,
+            ..PackedFormat::default()
+        }
+    }
+
+    #[test]
+    fn row_major() {
+        let msb = format(PixelOrder::RowMajor, BitOrder::MsbFirst);
+        assert_eq!(pack(&glyph(), &msb), [0x80, 0x60]);
+        let lsb = format(PixelOrder::RowMajor, BitOrder::LsbFirst);
+        assert_eq!(pack(&glyph(), &lsb), [0x01, 0x06]);
+    }
+
+    #[test]
+    fn unpadded_rows_share_words() {
+        let format = PackedFormat {
+            pad_rows: false,
+            ..PackedFormat::default()
+        };
+        assert_eq!(pack(&glyph(), &format), [0x8C]);
+    }
+
+    #[test]
+    fn column_major() {
+        let msb = format(PixelOrder::ColumnMajor, BitOrder::MsbFirst);
+        assert_eq!(pack(&glyph(), &msb), [0x80, 0x40, 0x40]);
+        let lsb = format(PixelOrder::ColumnMajor, BitOrder::LsbFirst);
+        assert_eq!(pack(&glyph(), &lsb), [0x01, 0x02, 0x02]);
+    }
+
+    #[test]
+    fn pages() {
+        let lsb = format(PixelOrder::Pages, BitOrder::LsbFirst);
+        assert_eq!(pack(&glyph(), &lsb), [0x01, 0x02, 0x02]);
+        let msb = format(PixelOrder::Pages, BitOrder::MsbFirst);
+        assert_eq!(pack(&glyph(), &msb), [0x80, 0x40, 0x40]);
+    }
+
+    #[test]
+    fn word_sizes() {
+        let format = PackedFormat {
+            word_size: WordSize::U16,
+            ..PackedFormat::default()
+        };
+        assert_eq!(pack(&glyph(), &format), [0x8000, 0x6000]);
+        let format = PackedFormat {
+            word_size: WordSize::U32,
+            bit_order: BitOrder::LsbFirst,
+            ..PackedFormat::default()
+        };
+        assert_eq!(pack(&glyph(), &format), [0x1, 0x6]);
+    }
+
+    #[test]
+    fn hex_output() {
+        let format = PackedFormat {
+            output: Output::Hex,
+            ..PackedFormat::default()
+        };
+        assert_eq!(write_glyph('A', &glyph(), &format), "0041:8060\n");
+    }
+}
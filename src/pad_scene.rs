@@ -1,15 +1,104 @@
-use crate::pad_view::PadView;
+use crate::anchor_picker::AnchorPicker;
+use crate::pad_view::{PadView, Paint, Tool};
 use crate::preview::Preview;
-use crate::{settings, SceneName, SceneResult, Settings};
+use crate::recovery;
+use crate::recovery::{Changes, Recovery};
+use crate::{settings, SceneName, SceneResult, Settings, HEIGHT, TITLE, WIDTH};
 use copypasta::{ClipboardContext, ClipboardProvider};
-use pixels_graphics_lib::prelude::SceneUpdateResult::Pop;
+use fontpad::font::Font;
+use fontpad::glyph::{Glyph, ShiftOptions};
+use fontpad::guide::GuideLayer;
+use fontpad::packed::{BitOrder, Output, PixelOrder, WordSize};
+use fontpad::{bdf, packed, project, rust_source};
+use pixels_graphics_lib::prelude::SceneUpdateResult::{Pop, Push};
 use pixels_graphics_lib::prelude::*;
 use pixels_graphics_lib::scenes::SceneUpdateResult::Nothing;
 use pixels_graphics_lib::ui::prelude::*;
-use pixels_graphics_lib::ui::styles::UiStyle;
+use pixels_graphics_lib::ui::styles::{AlertStyle, UiStyle};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
 
-const WIDTH_POS: Coord = Coord::new(24, 84);
-const HEIGHT_POS: Coord = Coord::new(24, 114);
+const WIDTH_POS: Coord = Coord::new(24, 98);
+const HEIGHT_POS: Coord = Coord::new(24, 128);
+const GLYPH_POS: Coord = Coord::new(180, 266);
+/// Rows per cell of slant when shearing, until changed in the menu
+const DEFAULT_SHEAR_ROWS: usize = 2;
+/// Cells moved by `Shift + arrow`, until changed in the menu
+const DEFAULT_LARGE_SHIFT: isize = 4;
+/// Seconds between writing unsaved work to the recovery file
+const AUTOSAVE_INTERVAL: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Menu {
+    File,
+    Open,
+    Save,
+    SaveAs,
+    New,
+    NewBlank,
+    NewTemplate(PixelFont),
+    ImportBdf,
+    ExportBdf,
+    ImportRust,
+    ExportRust,
+    Packed,
+    CopyPacked,
+    ExportPacked,
+    WordSizes,
+    WordSize(WordSize),
+    PixelOrders,
+    PixelOrder(PixelOrder),
+    BitOrders,
+    BitOrder(BitOrder),
+    PadRows,
+    Outputs,
+    Output(Output),
+    Transform,
+    RotateCw,
+    RotateCcw,
+    Transpose,
+    Shear,
+    ShearSteps,
+    /// Rows per cell of slant
+    ShearRows(usize),
+    ShiftWrap,
+    ShiftGuides,
+    LargeShifts,
+    /// Cells moved by `Shift + arrow`
+    LargeShift(isize),
+    Align,
+    Trim,
+    CenterH,
+    CenterV,
+    AlignBaseline,
+    AlignAll,
+    Guides,
+    ShowMetrics,
+    ResetMetrics,
+    EditGuideLayers,
+}
+
+/// What to do with the path chosen in a file dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileAction {
+    Open,
+    SaveAs,
+    ImportBdf,
+    ExportBdf,
+    ImportRust,
+    ExportRust,
+    ExportPacked,
+}
+
+/// Change that's waiting for the user to confirm it
+#[derive(Debug)]
+enum PendingAction {
+    /// Discard unsaved work and load a font, with its project file
+    ReplaceFont(Font, Option<String>),
+    /// `Align` menu item to run on every glyph, leaving them different sizes
+    Align(Menu),
+}
 
 pub struct PadScene {
     bg_color: Color,
@@ -23,17 +112,65 @@ pub struct PadScene {
     fill: Button,
     flip_h: Button,
     flip_v: Button,
+    prev_glyph: Button,
+    next_glyph: Button,
+    glyph_map: Button,
+    tools: Vec<(Tool, ToggleButton)>,
+    /// Draw rectangles and ellipses filled instead of outlined
+    fill_shapes: ToggleButton,
+    /// Bucket fill diagonally connected cells
+    fill_diagonal: ToggleButton,
+    invert: Button,
+    rotate: Button,
+    transpose: Button,
+    shear: Button,
+    anchor_picker: AnchorPicker,
     preview: Preview,
     infos: Vec<Text>,
+    menu: MenuBar<Menu>,
+    alert: Option<Alert>,
+    /// Alerts waiting to be shown after the current one is closed
+    queued_alerts: VecDeque<Alert>,
+    alert_style: AlertStyle,
+    file_action: Option<FileAction>,
     clipboard: ClipboardContext,
     settings: AppPrefs<Settings>,
-    next_update: Timer,
+    font: Font,
+    current: char,
+    /// Project file being edited, None if the font hasn't been saved
+    project_path: Option<String>,
+    changes: Changes,
+    autosave: Timer,
+    /// Unsaved work from a previous run, waiting for the user to choose whether to restore it
+    recovery: Option<Recovery>,
+    /// Change waiting for the user to answer the open alert
+    pending: Option<PendingAction>,
+    /// Last title set on the window
+    window_title: String,
+    /// Last position of the mouse while panning with the middle button
+    pan_from: Option<Coord>,
+    shear_rows: usize,
+    shift_options: ShiftOptions,
+    large_shift: isize,
+    /// Align menu items change every glyph, not just the current one
+    align_all: bool,
+    show_metrics: bool,
+    /// Guide layer changed by painting with shift held
+    active_layer: usize,
 }
 
 impl PadScene {
     pub fn new(style: &UiStyle) -> Box<Self> {
-        let settings = settings();
-        Box::new(PadScene {
+        let mut settings = settings();
+        let settings_error = project::validate(&settings.data.font).err();
+        if settings_error.is_some() {
+            settings.data.font = Settings::default().font;
+        }
+        let font = settings.data.font.clone();
+        let current = settings.data.current;
+        let project_path = settings.data.project_path.clone();
+        let glyph = font.glyph_or_new(current);
+        let mut scene = Box::new(PadScene {
             bg_color: style.background,
             result: Nothing,
             clipboard: ClipboardContext::new().expect("Unable to access clipboard"),
@@ -48,38 +185,763 @@ impl PadScene {
                     TextPos::px(coord!(4, HEIGHT_POS.y)),
                     (WHITE, PixelFont::Standard6x7),
                 ),
+                Text::new(
+                    "Anchor",
+                    TextPos::px(coord!(306, 18)),
+                    (WHITE, PixelFont::Standard6x7),
+                ),
             ],
-            preview: Preview::new(coord!(2, 144), &settings),
-            pad_view: PadView::new(coord!(60, 4), &settings),
-            fill: Button::new(coord!(4, 4), "Fill", Some(50), &style.button),
-            clear: Button::new(coord!(4, 24), "Clear", Some(50), &style.button),
-            flip_h: Button::new(coord!(4, 44), "Flip H", Some(50), &style.button),
-            flip_v: Button::new(coord!(4, 64), "Flip V", Some(50), &style.button),
-            font_width_inc: Button::new(coord!(30, 94), "+", Some(20), &style.button),
-            font_width_dec: Button::new(coord!(4, 94), "-", Some(20), &style.button),
-            font_height_inc: Button::new(coord!(30, 124), "+", Some(20), &style.button),
-            font_height_dec: Button::new(coord!(4, 124), "-", Some(20), &style.button),
+            preview: Preview::new(coord!(2, 158), &glyph),
+            pad_view: PadView::new(coord!(60, 18), current, &glyph),
+            fill: Button::new(coord!(4, 18), "Fill", Some(50), &style.button),
+            clear: Button::new(coord!(4, 38), "Clear", Some(50), &style.button),
+            flip_h: Button::new(coord!(4, 58), "Flip H", Some(50), &style.button),
+            flip_v: Button::new(coord!(4, 78), "Flip V", Some(50), &style.button),
+            font_width_inc: Button::new(coord!(30, 108), "+", Some(20), &style.button),
+            font_width_dec: Button::new(coord!(4, 108), "-", Some(20), &style.button),
+            font_height_inc: Button::new(coord!(30, 138), "+", Some(20), &style.button),
+            font_height_dec: Button::new(coord!(4, 138), "-", Some(20), &style.button),
+            prev_glyph: Button::new(coord!(60, 262), "<", Some(20), &style.button),
+            next_glyph: Button::new(coord!(280, 262), ">", Some(20), &style.button),
+            glyph_map: Button::new(coord!(4, 262), "Map", Some(50), &style.button),
+            anchor_picker: AnchorPicker::new(coord!(310, 28)),
+            tools: [
+                (Tool::Pencil, "Draw"),
+                (Tool::Select, "Select"),
+                (Tool::Line, "Line"),
+                (Tool::Rect, "Rect"),
+                (Tool::Ellipse, "Ellipse"),
+                (Tool::Bucket, "Bucket"),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (tool, name))| {
+                let pos = coord!(304, 72 + i * 20);
+                (
+                    tool,
+                    ToggleButton::new(pos, name, Some(52), &style.toggle_button),
+                )
+            })
+            .collect(),
+            fill_shapes: ToggleButton::new(
+                coord!(304, 196),
+                "Filled",
+                Some(52),
+                &style.toggle_button,
+            ),
+            fill_diagonal: ToggleButton::new(
+                coord!(304, 216),
+                "8-way",
+                Some(52),
+                &style.toggle_button,
+            ),
+            invert: Button::new(coord!(304, 240), "Invert", Some(52), &style.button),
+            rotate: Button::new(coord!(304, 262), "R", Some(16), &style.button),
+            transpose: Button::new(coord!(322, 262), "T", Some(16), &style.button),
+            shear: Button::new(coord!(340, 262), "K", Some(16), &style.button),
+            menu: MenuBar::new(
+                &style.menu,
+                Coord::default(),
+                (WIDTH, HEIGHT),
+                true,
+                &[
+                    MenuBarItem::new_menu(
+                        Menu::File,
+                        "File",
+                        &[
+                            (Menu::Open, "Open"),
+                            (Menu::Save, "Save"),
+                            (Menu::SaveAs, "Save As"),
+                            (Menu::ImportBdf, "Import BDF"),
+                            (Menu::ExportBdf, "Export BDF"),
+                            (Menu::ImportRust, "Import Rust"),
+                            (Menu::ExportRust, "Export Rust"),
+                        ],
+                    ),
+                    MenuBarItem::new_menu(
+                        Menu::New,
+                        "New",
+                        &[
+                            (Menu::NewBlank, "Blank"),
+                            (Menu::NewTemplate(PixelFont::Limited3x5), "Limited 3x5"),
+                            (Menu::NewTemplate(PixelFont::Standard4x4), "Standard 4x4"),
+                            (Menu::NewTemplate(PixelFont::Standard4x5), "Standard 4x5"),
+                            (Menu::NewTemplate(PixelFont::Standard6x7), "Standard 6x7"),
+                            (Menu::NewTemplate(PixelFont::Standard8x10), "Standard 8x10"),
+                            (Menu::NewTemplate(PixelFont::Script8x8), "Script 8x8"),
+                            (Menu::NewTemplate(PixelFont::Outline7x9), "Outline 7x9"),
+                        ],
+                    ),
+                    MenuBarItem::new(
+                        Menu::Packed,
+                        "Packed",
+                        vec![
+                            MenuBarItem::new_button(Menu::CopyPacked, "Copy glyph"),
+                            MenuBarItem::new_button(Menu::ExportPacked, "Export font"),
+                            MenuBarItem::new_options(
+                                Menu::WordSizes,
+                                "Word size",
+                                &[
+                                    (Menu::WordSize(WordSize::U8), "u8"),
+                                    (Menu::WordSize(WordSize::U16), "u16"),
+                                    (Menu::WordSize(WordSize::U32), "u32"),
+                                ],
+                                0,
+                            ),
+                            MenuBarItem::new_options(
+                                Menu::PixelOrders,
+                                "Order",
+                                &[
+                                    (Menu::PixelOrder(PixelOrder::RowMajor), "Row major"),
+                                    (Menu::PixelOrder(PixelOrder::ColumnMajor), "Column major"),
+                                    (Menu::PixelOrder(PixelOrder::Pages), "Vertical pages"),
+                                ],
+                                0,
+                            ),
+                            MenuBarItem::new_options(
+                                Menu::BitOrders,
+                                "Bit order",
+                                &[
+                                    (Menu::BitOrder(BitOrder::MsbFirst), "MSB first"),
+                                    (Menu::BitOrder(BitOrder::LsbFirst), "LSB first"),
+                                ],
+                                0,
+                            ),
+                            MenuBarItem::new_checkable(Menu::PadRows, "Pad rows", true),
+                            MenuBarItem::new_options(
+                                Menu::Outputs,
+                                "Output",
+                                &[
+                                    (Menu::Output(Output::Rust), "Rust"),
+                                    (Menu::Output(Output::C), "C"),
+                                    (Menu::Output(Output::Hex), "Hex"),
+                                ],
+                                0,
+                            ),
+                        ],
+                    ),
+                    MenuBarItem::new(
+                        Menu::Transform,
+                        "Transform",
+                        vec![
+                            MenuBarItem::new_button(Menu::RotateCw, "Rotate CW"),
+                            MenuBarItem::new_button(Menu::RotateCcw, "Rotate CCW"),
+                            MenuBarItem::new_button(Menu::Transpose, "Transpose"),
+                            MenuBarItem::new_button(Menu::Shear, "Shear"),
+                            MenuBarItem::new_options(
+                                Menu::ShearSteps,
+                                "Shear every",
+                                &[
+                                    (Menu::ShearRows(1), "1 row"),
+                                    (Menu::ShearRows(2), "2 rows"),
+                                    (Menu::ShearRows(3), "3 rows"),
+                                    (Menu::ShearRows(4), "4 rows"),
+                                ],
+                                DEFAULT_SHEAR_ROWS - 1,
+                            ),
+                            MenuBarItem::new_checkable(Menu::ShiftWrap, "Wrap shifts", true),
+                            MenuBarItem::new_checkable(Menu::ShiftGuides, "Shift guides", false),
+                            MenuBarItem::new_options(
+                                Menu::LargeShifts,
+                                "Shift + arrow",
+                                &[
+                                    (Menu::LargeShift(2), "2 cells"),
+                                    (Menu::LargeShift(4), "4 cells"),
+                                    (Menu::LargeShift(8), "8 cells"),
+                                ],
+                                1,
+                            ),
+                        ],
+                    ),
+                    MenuBarItem::new(
+                        Menu::Align,
+                        "Align",
+                        vec![
+                            MenuBarItem::new_button(Menu::Trim, "Trim"),
+                            MenuBarItem::new_button(Menu::CenterH, "Center horizontally"),
+                            MenuBarItem::new_button(Menu::CenterV, "Center vertically"),
+                            MenuBarItem::new_button(Menu::AlignBaseline, "Align to baseline"),
+                            MenuBarItem::new_checkable(Menu::AlignAll, "All glyphs", false),
+                        ],
+                    ),
+                    MenuBarItem::new(
+                        Menu::Guides,
+                        "Guides",
+                        vec![
+                            MenuBarItem::new_checkable(Menu::ShowMetrics, "Show metrics", true),
+                            MenuBarItem::new_button(Menu::ResetMetrics, "Reset metrics"),
+                            MenuBarItem::new_button(Menu::EditGuideLayers, "Edit layers"),
+                        ],
+                    ),
+                ],
+            ),
+            alert: None,
+            queued_alerts: VecDeque::new(),
+            alert_style: style.alert.clone(),
+            file_action: None,
             settings,
-            next_update: Timer::new_once(0.2),
-        })
+            changes: Changes::new(&font),
+            font,
+            autosave: Timer::new_with_delay(AUTOSAVE_INTERVAL, AUTOSAVE_INTERVAL),
+            recovery: None,
+            pending: None,
+            current,
+            project_path,
+            window_title: String::new(),
+            pan_from: None,
+            shear_rows: DEFAULT_SHEAR_ROWS,
+            shift_options: ShiftOptions::default(),
+            large_shift: DEFAULT_LARGE_SHIFT,
+            align_all: false,
+            show_metrics: true,
+            active_layer: 0,
+        });
+        scene.update_packed_menu();
+        scene.set_tool(Tool::default());
+        scene.update_guide_layers();
+        scene.offer_recovery();
+        if let Some(err) = settings_error {
+            scene.show_error("Unable to restore font", err);
+        }
+        scene
     }
 }
 
 impl PadScene {
-    fn copy(&mut self) {
-        let output = self.pad_view.copy_str();
-        self.clipboard
-            .set_contents(output.clone())
-            .unwrap_or_else(|err| panic!("Error copying: {output}: {err:?}"));
-        self.settings.data.dots = self.pad_view.dots.clone();
-        self.settings.data.width = self.pad_view.size.0;
-        self.settings.data.height = self.pad_view.size.1;
-        self.settings.data.guides = self.pad_view.guides.clone();
+    /// Store the glyph being edited in the font
+    fn commit_glyph(&mut self) {
+        self.font
+            .set_glyph(self.current, self.pad_view.glyph().clone());
+    }
+
+    fn select_glyph(&mut self, chr: char) {
+        self.commit_glyph();
+        self.current = chr;
+        self.pad_view.set_glyph(chr, &self.font.glyph_or_new(chr));
+        self.preview.update(&self.pad_view);
+    }
+
+    fn open_glyph_map(&mut self) {
+        self.commit_glyph();
+        self.result = Push(false, SceneName::GlyphMap(self.font.clone(), self.current));
+    }
+
+    fn show_error(&mut self, title: &str, err: anyhow::Error) {
+        eprintln!("{title}: {err:?}");
+        self.show_alert(Alert::new_warning(
+            &[title, &err.to_string()],
+            WIDTH,
+            HEIGHT,
+            &self.alert_style,
+        ));
+    }
+
+    /// Show `alert`, or queue it if another alert is open
+    fn show_alert(&mut self, alert: Alert) {
+        if self.alert.is_some() {
+            self.queued_alerts.push_back(alert);
+        } else {
+            self.alert = Some(alert);
+        }
+    }
+
+    /// Replace the whole font, keeping the current character selected if it's in the new font
+    ///
+    /// The font is no longer associated with a project file and the undo history is cleared
+    fn set_font(&mut self, font: Font) {
+        self.font = font;
+        self.project_path = None;
+        if !self.font.glyphs.contains_key(&self.current) {
+            self.current = self.font.first_char();
+        }
+        self.pad_view
+            .set_glyph(self.current, &self.font.glyph_or_new(self.current));
+        self.pad_view.clear_history();
+        self.update_guide_layers();
+    }
+
+    /// Replace the whole font with `font`, asking first if there are unsaved changes
+    ///
+    /// If `project_path` is set the font is treated as opened from that project
+    fn replace_font(&mut self, font: Font, project_path: Option<String>) {
+        self.commit_glyph();
+        if !self.changes.is_unsaved(&self.font) {
+            self.load_font(font, project_path);
+            return;
+        }
+        self.show_alert(Alert::new_question(
+            &["Discard unsaved changes?"],
+            "Cancel",
+            "Discard",
+            WIDTH,
+            HEIGHT,
+            &self.alert_style,
+        ));
+        self.pending = Some(PendingAction::ReplaceFont(font, project_path));
+    }
+
+    fn load_font(&mut self, font: Font, project_path: Option<String>) {
+        self.set_font(font);
+        if let Some(path) = project_path {
+            self.project_path = Some(path);
+            self.save_settings();
+        }
+    }
+
+    fn set_tool(&mut self, tool: Tool) {
+        self.pad_view.set_tool(tool);
+        for (button_tool, button) in &mut self.tools {
+            button.set_selected(*button_tool == tool);
+        }
+    }
+
+    /// Show the font's metrics on the pad and preview, if enabled
+    fn update_metrics(&mut self) {
+        let metrics = self.show_metrics.then(|| self.font.metrics());
+        self.pad_view.set_metrics(metrics);
+        self.preview.update(&self.pad_view);
+    }
+
+    /// Show the font's guide layers on the pad and preview
+    fn update_guide_layers(&mut self) {
+        self.active_layer = self
+            .active_layer
+            .min(self.font.guide_layers.len().saturating_sub(1));
+        self.pad_view.set_guide_layers(&self.font.guide_layers);
+        self.update_metrics();
+    }
+
+    fn open_guide_layers(&mut self) {
+        self.commit_glyph();
+        self.result = Push(
+            false,
+            SceneName::GuideLayers(self.font.guide_layers.clone(), self.active_layer),
+        );
+    }
+
+    /// Replace the guide layers with those from the layer editor, the current glyph is reloaded to pick up shared marks
+    ///
+    /// Undo history is cleared as its glyphs have guides for the old layers
+    fn set_guide_layers(&mut self, layers: Vec<(Option<usize>, GuideLayer)>, active: usize) {
+        self.font.set_guide_layers(layers);
+        self.active_layer = active;
+        let glyph = self.font.glyph_or_new(self.current);
+        self.pad_view.set_glyph(self.current, &glyph);
+        self.pad_view.clear_history();
+        self.update_guide_layers();
+    }
+
+    /// Set the metric for `key` from the row under the mouse, heights are measured up to it from the baseline
+    fn set_metric(&mut self, key: KeyCode, mouse: Coord) {
+        let Some(row) = self.pad_view.row_at(mouse) else {
+            return;
+        };
+        let mut metrics = self.font.metrics();
+        let height = (metrics.baseline + 1).saturating_sub(row);
+        match key {
+            KeyCode::KeyB => metrics.baseline = row,
+            KeyCode::KeyA => metrics.ascent = height,
+            KeyCode::KeyH => metrics.cap_height = height,
+            KeyCode::KeyX => metrics.x_height = height,
+            KeyCode::KeyD => metrics.descent = row.saturating_sub(metrics.baseline),
+            _ => return,
+        }
+        self.font.metrics = Some(metrics);
+        self.update_metrics();
+    }
+
+    /// Check the menu items matching the packed format in settings
+    fn update_packed_menu(&mut self) {
+        let format = self.settings.data.packed;
+        for (group, item) in [
+            (Menu::WordSizes, Menu::WordSize(format.word_size)),
+            (Menu::PixelOrders, Menu::PixelOrder(format.order)),
+            (Menu::BitOrders, Menu::BitOrder(format.bit_order)),
+            (Menu::Outputs, Menu::Output(format.output)),
+        ] {
+            self.menu.uncheck_all_children(group);
+            self.menu.set_checked(item, true);
+        }
+        self.menu.set_checked(Menu::PadRows, format.pad_rows);
+    }
+
+    fn on_menu_click(&mut self, menu: Menu) {
+        let packed = &mut self.settings.data.packed;
+        match menu {
+            Menu::File
+            | Menu::New
+            | Menu::Packed
+            | Menu::WordSizes
+            | Menu::PixelOrders
+            | Menu::BitOrders
+            | Menu::Outputs
+            | Menu::Transform
+            | Menu::ShearSteps
+            | Menu::LargeShifts
+            | Menu::Align
+            | Menu::Guides => {}
+            Menu::NewBlank => {
+                let (width, height) = self.pad_view.size();
+                self.replace_font(Font::new(width, height), None)
+            }
+            Menu::NewTemplate(pixel_font) => {
+                self.replace_font(Font::from_pixel_font(pixel_font), None)
+            }
+            Menu::Open => self.open(),
+            Menu::Save => self.save(),
+            Menu::SaveAs => self.save_as(),
+            Menu::ImportBdf => {
+                self.file_action = Some(FileAction::ImportBdf);
+                self.result = Push(false, SceneName::LoadFile(String::from("bdf")));
+            }
+            Menu::ExportBdf => {
+                self.commit_glyph();
+                self.file_action = Some(FileAction::ExportBdf);
+                self.result = Push(false, SceneName::SaveFile(String::from("bdf"), None));
+            }
+            Menu::ImportRust => {
+                self.file_action = Some(FileAction::ImportRust);
+                self.result = Push(false, SceneName::LoadFile(String::from("rs")));
+            }
+            Menu::ExportRust => {
+                self.commit_glyph();
+                self.file_action = Some(FileAction::ExportRust);
+                self.result = Push(false, SceneName::SaveFile(String::from("rs"), None));
+            }
+            Menu::CopyPacked => self.copy(true),
+            Menu::ExportPacked => {
+                let ext = packed.output.extension().to_string();
+                self.commit_glyph();
+                self.file_action = Some(FileAction::ExportPacked);
+                self.result = Push(false, SceneName::SaveFile(ext, None));
+            }
+            Menu::WordSize(value) => packed.word_size = value,
+            Menu::PixelOrder(value) => packed.order = value,
+            Menu::BitOrder(value) => packed.bit_order = value,
+            Menu::PadRows => packed.pad_rows = !packed.pad_rows,
+            Menu::Output(value) => packed.output = value,
+            Menu::RotateCw => self.pad_view.rotate_cw(),
+            Menu::RotateCcw => self.pad_view.rotate_ccw(),
+            Menu::Transpose => self.pad_view.transpose(),
+            Menu::Shear => self.pad_view.shear(self.shear_rows),
+            Menu::ShearRows(rows) => {
+                self.shear_rows = rows;
+                self.menu.uncheck_all_children(Menu::ShearSteps);
+                self.menu.set_checked(menu, true);
+            }
+            Menu::ShiftWrap => {
+                self.shift_options.wrap = !self.shift_options.wrap;
+                self.menu.set_checked(menu, self.shift_options.wrap);
+            }
+            Menu::ShiftGuides => {
+                self.shift_options.guides = !self.shift_options.guides;
+                self.menu.set_checked(menu, self.shift_options.guides);
+            }
+            Menu::Trim | Menu::CenterH | Menu::CenterV | Menu::AlignBaseline => {
+                self.align(menu, false)
+            }
+            Menu::AlignAll => {
+                self.align_all = !self.align_all;
+                self.menu.set_checked(menu, self.align_all);
+            }
+            Menu::ShowMetrics => {
+                self.show_metrics = !self.show_metrics;
+                self.menu.set_checked(menu, self.show_metrics);
+                self.update_metrics();
+            }
+            Menu::EditGuideLayers => self.open_guide_layers(),
+            Menu::ResetMetrics => {
+                self.font.metrics = None;
+                self.update_metrics();
+            }
+            Menu::LargeShift(cells) => {
+                self.large_shift = cells;
+                self.menu.uncheck_all_children(Menu::LargeShifts);
+                self.menu.set_checked(menu, true);
+            }
+        }
+        self.preview.update(&self.pad_view);
+        if matches!(
+            menu,
+            Menu::WordSize(_)
+                | Menu::PixelOrder(_)
+                | Menu::BitOrder(_)
+                | Menu::PadRows
+                | Menu::Output(_)
+        ) {
+            self.settings.save();
+            self.update_packed_menu();
+        }
+    }
+
+    /// Run the `Align` menu item `menu` on the current glyph, and every other glyph if `All glyphs` is checked
+    ///
+    /// Editing every glyph is a single undo step, if it would leave glyphs that were all the same size
+    /// at different sizes the user is asked first, unless `confirmed`
+    fn align(&mut self, menu: Menu, confirmed: bool) {
+        let baseline = self.font.metrics().baseline;
+        let edit = |glyph: &mut Glyph| match menu {
+            Menu::Trim => glyph.trim(),
+            Menu::CenterH => glyph.center_horizontally(),
+            Menu::CenterV => glyph.center_vertically(),
+            Menu::AlignBaseline => glyph.align_to_baseline(baseline),
+            _ => {}
+        };
+        if !self.align_all {
+            self.pad_view.transform(edit);
+            return;
+        }
+        self.commit_glyph();
+        let sizes_before: HashSet<(usize, usize)> =
+            self.font.glyphs.values().map(Glyph::size).collect();
+        let sizes_after: HashSet<(usize, usize)> = self
+            .font
+            .glyphs
+            .values()
+            .map(|glyph| {
+                let mut glyph = glyph.clone();
+                edit(&mut glyph);
+                glyph.size()
+            })
+            .collect();
+        if !confirmed && sizes_before.len() == 1 && sizes_after.len() > 1 {
+            self.show_alert(Alert::new_question(
+                &[
+                    "Glyphs will be different sizes,",
+                    "Rust export needs them all the same",
+                ],
+                "Cancel",
+                "Continue",
+                WIDTH,
+                HEIGHT,
+                &self.alert_style,
+            ));
+            self.pending = Some(PendingAction::Align(menu));
+            return;
+        }
+        self.pad_view.transform_font(&mut self.font, |font| {
+            font.glyphs.values_mut().for_each(edit)
+        });
+    }
+
+    /// Grow or shrink the glyph, or every glyph and the default size if `all_glyphs`
+    fn resize(&mut self, width: isize, height: isize, all_glyphs: bool) {
+        let anchor = self.anchor_picker.anchor();
+        if all_glyphs {
+            self.pad_view
+                .transform_font(&mut self.font, |font| font.resize_by(width, height, anchor));
+            self.update_metrics();
+        } else if width != 0 {
+            self.pad_view.change_width(width, anchor);
+        } else {
+            self.pad_view.change_height(height, anchor);
+        }
+    }
+
+    fn on_file_chosen(&mut self, action: FileAction, path: String) {
+        match action {
+            FileAction::Open => {
+                match fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|text| project::parse(&text))
+                {
+                    Ok(font) => self.replace_font(font, Some(path)),
+                    Err(err) => self.show_error("Unable to open project", err),
+                }
+            }
+            FileAction::SaveAs => self.write_project(path),
+            FileAction::ImportBdf => {
+                match fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|text| bdf::parse(&text))
+                {
+                    Ok(font) => self.replace_font(font, None),
+                    Err(err) => self.show_error("Unable to import BDF", err),
+                }
+            }
+            FileAction::ExportBdf => {
+                let name = Path::new(&path)
+                    .file_stem()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| String::from("fontpad"));
+                if let Err(err) = fs::write(&path, bdf::write(&self.font, &name)) {
+                    self.show_error("Unable to export BDF", err.into());
+                }
+            }
+            FileAction::ImportRust => {
+                match fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|text| rust_source::parse(&text))
+                {
+                    Ok(font) => self.replace_font(font, None),
+                    Err(err) => self.show_error("Unable to import Rust", err),
+                }
+            }
+            FileAction::ExportRust => {
+                if let Err(err) = rust_source::write(&self.font)
+                    .and_then(|code| fs::write(&path, code).map_err(anyhow::Error::from))
+                {
+                    self.show_error("Unable to export Rust", err);
+                }
+            }
+            FileAction::ExportPacked => {
+                let output = packed::write(&self.font, &self.settings.data.packed);
+                if let Err(err) = fs::write(&path, output) {
+                    self.show_error("Unable to export packed font", err.into());
+                }
+            }
+        }
+    }
+
+    fn open(&mut self) {
+        self.file_action = Some(FileAction::Open);
+        self.result = Push(false, SceneName::LoadFile(String::from(project::EXTENSION)));
+    }
+
+    /// Save to the current project file, or ask for a path if there isn't one
+    fn save(&mut self) {
+        match self.project_path.clone() {
+            Some(path) => self.write_project(path),
+            None => self.save_as(),
+        }
+    }
+
+    fn save_as(&mut self) {
+        self.file_action = Some(FileAction::SaveAs);
+        self.result = Push(
+            false,
+            SceneName::SaveFile(String::from(project::EXTENSION), self.project_path.clone()),
+        );
+    }
+
+    fn write_project(&mut self, path: String) {
+        self.commit_glyph();
+        match project::write(&self.font)
+            .and_then(|text| fs::write(&path, text).map_err(anyhow::Error::from))
+        {
+            Ok(()) => {
+                self.project_path = Some(path);
+                self.save_settings();
+            }
+            Err(err) => self.show_error("Unable to save project", err),
+        }
+    }
+
+    /// Store the font and open project in the app preferences
+    fn save_settings(&mut self) {
+        self.commit_glyph();
+        self.settings.data.font = self.font.clone();
+        self.settings.data.current = self.current;
+        self.settings.data.project_path = self.project_path.clone();
         self.settings.save();
+        self.changes.set_saved(&self.font);
+        if let Err(err) = recovery::clear() {
+            eprintln!("{err:?}");
+        }
+    }
+
+    /// Write the font to the recovery file if it's changed since it was last saved
+    fn autosave(&mut self) {
+        self.commit_glyph();
+        if !self.changes.needs_autosave(&self.font) {
+            return;
+        }
+        let recovery = Recovery {
+            font: self.font.clone(),
+            current: self.current,
+            project_path: self.project_path.clone(),
+        };
+        match recovery::save(&recovery) {
+            Ok(()) => self.changes.set_autosaved(recovery.font),
+            Err(err) => eprintln!("{err:?}"),
+        }
+    }
+
+    /// Ask if work from the recovery file should be restored, if there is any
+    fn offer_recovery(&mut self) {
+        match recovery::load() {
+            Ok(Some(recovery)) if recovery.font != self.font => {
+                self.show_alert(Alert::new_question(
+                    &["Restore unsaved work?"],
+                    "Discard",
+                    "Restore",
+                    WIDTH,
+                    HEIGHT,
+                    &self.alert_style,
+                ));
+                self.recovery = Some(recovery);
+            }
+            Ok(Some(_)) => {
+                if let Err(err) = recovery::clear() {
+                    eprintln!("{err:?}");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("Unable to load recovery file: {err:?}"),
+        }
+    }
+
+    fn on_alert_result(&mut self, result: AlertResult) {
+        self.alert = self.queued_alerts.pop_front();
+        if let Some(pending) = self.pending.take() {
+            if result == AlertResult::Positive {
+                match pending {
+                    PendingAction::ReplaceFont(font, project_path) => {
+                        self.load_font(font, project_path)
+                    }
+                    PendingAction::Align(menu) => self.align(menu, true),
+                }
+                self.preview.update(&self.pad_view);
+            }
+            return;
+        }
+        let Some(recovery) = self.recovery.take() else {
+            return;
+        };
+        match result {
+            AlertResult::Positive => {
+                self.set_font(recovery.font);
+                self.project_path = recovery.project_path;
+                self.select_glyph(recovery.current);
+                self.autosave();
+            }
+            AlertResult::Negative => {
+                if let Err(err) = recovery::clear() {
+                    eprintln!("{err:?}");
+                }
+            }
+        }
+    }
+
+    /// Copy the glyph as a bool array, or packed in the format from settings
+    ///
+    /// If cells are selected (and not copying packed) they're copied to the internal clipboard instead
+    fn copy(&mut self, packed: bool) {
+        if !packed && self.pad_view.copy_selection() {
+            return;
+        }
+        self.pad_view.clear_region_clipboard();
+        let output = if packed {
+            packed::write_glyph(
+                self.current,
+                self.pad_view.glyph(),
+                &self.settings.data.packed,
+            )
+        } else {
+            self.pad_view.glyph().copy_str()
+        };
+        if let Err(err) = self.clipboard.set_contents(output) {
+            self.show_error("Unable to copy", anyhow::anyhow!(err));
+        }
         self.preview.add_to_history();
     }
 
-    fn paste(&mut self) {
+    /// Paste cells copied from a selection at the cursor, or a glyph from the clipboard
+    fn paste(&mut self, mouse: Coord) {
+        if self.pad_view.paste_region(mouse) {
+            self.preview.update(&self.pad_view);
+            return;
+        }
         match self.clipboard.get_contents() {
             Ok(contents) => {
                 self.pad_view
@@ -104,50 +966,127 @@ impl Scene<SceneResult, SceneName> for PadScene {
         self.font_height_inc.render(graphics, mouse);
         self.font_width_dec.render(graphics, mouse);
         self.font_width_inc.render(graphics, mouse);
+        self.prev_glyph.render(graphics, mouse);
+        self.next_glyph.render(graphics, mouse);
+        self.glyph_map.render(graphics, mouse);
+        self.anchor_picker.render(graphics, mouse);
+        self.tools
+            .iter()
+            .for_each(|(_, button)| button.render(graphics, mouse));
+        self.fill_shapes.render(graphics, mouse);
+        self.fill_diagonal.render(graphics, mouse);
+        self.invert.render(graphics, mouse);
+        self.rotate.render(graphics, mouse);
+        self.transpose.render(graphics, mouse);
+        self.shear.render(graphics, mouse);
         self.infos.iter().for_each(|t| t.render(graphics));
         graphics.draw_text(
-            &format!("{}", self.pad_view.size.0),
+            &format!("{}", self.pad_view.size().0),
             TextPos::px(WIDTH_POS),
             (WHITE, PixelFont::Standard6x7),
         );
         graphics.draw_text(
-            &format!("{}", self.pad_view.size.1),
+            &format!("{}", self.pad_view.size().1),
             TextPos::px(HEIGHT_POS),
             (WHITE, PixelFont::Standard6x7),
         );
+        graphics.draw_text(
+            &format!("'{}' U+{:04X}", self.current, self.current as u32),
+            TextPos::px(GLYPH_POS),
+            (WHITE, PixelFont::Standard6x7, Positioning::Center),
+        );
+        self.menu.render(graphics, mouse);
+        if let Some(alert) = &self.alert {
+            alert.render(graphics, mouse);
+        }
     }
 
-    fn on_key_up(&mut self, key: KeyCode, _: &MouseData, held: &FxHashSet<KeyCode>) {
+    fn on_key_up(&mut self, key: KeyCode, mouse: &MouseData, held: &FxHashSet<KeyCode>) {
+        if self.alert.is_some() {
+            match key {
+                KeyCode::Escape => self.on_alert_result(AlertResult::Negative),
+                KeyCode::Enter => self.on_alert_result(AlertResult::Positive),
+                _ => {}
+            }
+            return;
+        }
         let modifier_pressed = held.contains(&KeyCode::ControlLeft)
             || held.contains(&KeyCode::ControlRight)
             || held.contains(&KeyCode::SuperLeft)
             || held.contains(&KeyCode::SuperRight);
+        let shift_pressed =
+            held.contains(&KeyCode::ShiftLeft) || held.contains(&KeyCode::ShiftRight);
         match key {
-            KeyCode::Escape => self.result = Pop(None),
-            KeyCode::KeyC => {
-                if modifier_pressed {
-                    self.copy()
+            KeyCode::Escape if !self.pad_view.deselect() => {
+                self.autosave();
+                self.result = Pop(None);
+            }
+            KeyCode::KeyZ if modifier_pressed => {
+                if shift_pressed {
+                    self.pad_view.redo(&mut self.font);
+                } else {
+                    self.pad_view.undo(&mut self.font);
+                }
+                self.current = self.pad_view.chr();
+                self.update_metrics();
+            }
+            KeyCode::KeyC if modifier_pressed => self.copy(shift_pressed),
+            KeyCode::KeyX if modifier_pressed => {
+                self.pad_view.cut_selection();
+                self.preview.update(&self.pad_view);
+            }
+            KeyCode::KeyV if modifier_pressed => self.paste(mouse.xy),
+            KeyCode::KeyA if modifier_pressed => {
+                self.set_tool(Tool::Select);
+                self.pad_view.select_all();
+            }
+            KeyCode::KeyO if modifier_pressed => self.open(),
+            KeyCode::KeyS if modifier_pressed => {
+                if shift_pressed {
+                    self.save_as();
+                } else {
+                    self.save();
                 }
             }
-            KeyCode::KeyV => {
-                if modifier_pressed {
-                    self.paste()
+            KeyCode::PageUp => self.select_glyph(self.font.prev_char(self.current)),
+            KeyCode::PageDown => self.select_glyph(self.font.next_char(self.current)),
+            KeyCode::KeyM if !modifier_pressed => self.open_glyph_map(),
+            KeyCode::KeyL if !modifier_pressed => self.open_guide_layers(),
+            KeyCode::KeyR if !modifier_pressed => {
+                if shift_pressed {
+                    self.pad_view.rotate_ccw();
+                } else {
+                    self.pad_view.rotate_cw();
                 }
+                self.preview.update(&self.pad_view);
             }
-            KeyCode::ArrowUp => {
-                self.pad_view.move_up();
+            KeyCode::KeyT if !modifier_pressed => {
+                self.pad_view.transpose();
                 self.preview.update(&self.pad_view);
             }
-            KeyCode::ArrowDown => {
-                self.pad_view.move_down();
+            KeyCode::KeyK if !modifier_pressed => {
+                self.pad_view.shear(self.shear_rows);
                 self.preview.update(&self.pad_view);
             }
-            KeyCode::ArrowLeft => {
-                self.pad_view.move_left();
+            KeyCode::KeyB | KeyCode::KeyA | KeyCode::KeyH | KeyCode::KeyX | KeyCode::KeyD
+                if !modifier_pressed =>
+            {
+                self.set_metric(key, mouse.xy)
+            }
+            KeyCode::KeyI if !modifier_pressed => {
+                self.pad_view.invert();
                 self.preview.update(&self.pad_view);
             }
-            KeyCode::ArrowRight => {
-                self.pad_view.move_right();
+            KeyCode::Digit0 => self.pad_view.reset_zoom(),
+            KeyCode::ArrowUp | KeyCode::ArrowDown | KeyCode::ArrowLeft | KeyCode::ArrowRight => {
+                let step = if shift_pressed { self.large_shift } else { 1 };
+                let (x, y) = match key {
+                    KeyCode::ArrowUp => (0, -step),
+                    KeyCode::ArrowDown => (0, step),
+                    KeyCode::ArrowLeft => (-step, 0),
+                    _ => (step, 0),
+                };
+                self.pad_view.shift(x, y, self.shift_options);
                 self.preview.update(&self.pad_view);
             }
             _ => {}
@@ -162,24 +1101,40 @@ impl Scene<SceneResult, SceneName> for PadScene {
         held: &FxHashSet<KeyCode>,
     ) {
         if mouse_button == MouseButton::Left {
+            if let Some(alert) = &mut self.alert {
+                if let Some(result) = alert.on_mouse_click(down_at, mouse.xy) {
+                    self.on_alert_result(result);
+                }
+                return;
+            }
+            let menu_was_expanded = self.menu.is_expanded();
+            if let Some(menu) = self.menu.on_mouse_click(down_at, mouse.xy) {
+                self.on_menu_click(menu);
+                return;
+            }
+            if menu_was_expanded {
+                return;
+            }
+            let shift_pressed =
+                held.contains(&KeyCode::ShiftLeft) || held.contains(&KeyCode::ShiftRight);
             if self.clear.on_mouse_click(down_at, mouse.xy) {
-                if held.contains(&KeyCode::ShiftLeft) || held.contains(&KeyCode::ShiftRight) {
+                if shift_pressed {
                     self.pad_view.clear_guides();
                 } else {
                     self.pad_view.clear();
                 }
             }
             if self.font_height_inc.on_mouse_click(down_at, mouse.xy) {
-                self.pad_view.change_height(1);
+                self.resize(0, 1, shift_pressed);
             }
             if self.font_height_dec.on_mouse_click(down_at, mouse.xy) {
-                self.pad_view.change_height(-1);
+                self.resize(0, -1, shift_pressed);
             }
             if self.font_width_inc.on_mouse_click(down_at, mouse.xy) {
-                self.pad_view.change_width(1);
+                self.resize(1, 0, shift_pressed);
             }
             if self.font_width_dec.on_mouse_click(down_at, mouse.xy) {
-                self.pad_view.change_width(-1);
+                self.resize(-1, 0, shift_pressed);
             }
             if self.fill.on_mouse_click(down_at, mouse.xy) {
                 self.pad_view.fill();
@@ -190,25 +1145,130 @@ impl Scene<SceneResult, SceneName> for PadScene {
             if self.flip_v.on_mouse_click(down_at, mouse.xy) {
                 self.pad_view.flip_v();
             }
+            if self.prev_glyph.on_mouse_click(down_at, mouse.xy) {
+                self.select_glyph(self.font.prev_char(self.current));
+            }
+            if self.next_glyph.on_mouse_click(down_at, mouse.xy) {
+                self.select_glyph(self.font.next_char(self.current));
+            }
+            self.anchor_picker.on_mouse_click(down_at, mouse.xy);
+            let clicked_tool = self.tools.iter_mut().find_map(|(tool, button)| {
+                button.on_mouse_click(down_at, mouse.xy).then_some(*tool)
+            });
+            if let Some(tool) = clicked_tool {
+                self.set_tool(tool);
+            }
+            if self.fill_shapes.on_mouse_click(down_at, mouse.xy) {
+                let filled = !self.pad_view.fill_shapes();
+                self.pad_view.set_fill_shapes(filled);
+                self.fill_shapes.set_selected(filled);
+            }
+            if self.fill_diagonal.on_mouse_click(down_at, mouse.xy) {
+                let diagonal = !self.pad_view.fill_diagonal();
+                self.pad_view.set_fill_diagonal(diagonal);
+                self.fill_diagonal.set_selected(diagonal);
+            }
+            if self.invert.on_mouse_click(down_at, mouse.xy) {
+                self.pad_view.invert();
+            }
+            if self.rotate.on_mouse_click(down_at, mouse.xy) {
+                if shift_pressed {
+                    self.pad_view.rotate_ccw();
+                } else {
+                    self.pad_view.rotate_cw();
+                }
+            }
+            if self.transpose.on_mouse_click(down_at, mouse.xy) {
+                self.pad_view.transpose();
+            }
+            if self.shear.on_mouse_click(down_at, mouse.xy) {
+                self.pad_view.shear(self.shear_rows);
+            }
+            if self.glyph_map.on_mouse_click(down_at, mouse.xy) {
+                self.open_glyph_map();
+            }
             self.preview.update(&self.pad_view);
         }
     }
 
+    fn on_mouse_up(&mut self, _: &MouseData, mouse_button: MouseButton, _: &FxHashSet<KeyCode>) {
+        if matches!(mouse_button, MouseButton::Left | MouseButton::Right) {
+            self.pad_view.end_stroke();
+        }
+    }
+
+    fn on_scroll(
+        &mut self,
+        mouse: &MouseData,
+        x_diff: isize,
+        y_diff: isize,
+        _: &FxHashSet<KeyCode>,
+    ) {
+        if self.pad_view.bounds().contains(mouse.xy) {
+            self.pad_view.zoom_by(-y_diff.signum(), mouse.xy);
+            self.pad_view.pan_by(coord!(-x_diff, 0));
+        }
+    }
+
     fn update(
         &mut self,
         timing: &Timing,
         mouse: &MouseData,
         held: &FxHashSet<KeyCode>,
-        _: &Window,
+        window: &Window,
     ) -> SceneUpdateResult<SceneResult, SceneName> {
-        if mouse.is_down(MouseButton::Left).is_some() && self.next_update.update(timing) {
-            self.pad_view.on_mouse_update(
-                mouse.xy,
-                held.contains(&KeyCode::ShiftLeft) || held.contains(&KeyCode::ShiftRight),
-            );
+        let title = match &self.project_path {
+            Some(path) => format!("{TITLE} - {path}"),
+            None => String::from(TITLE),
+        };
+        if title != self.window_title {
+            window.set_title(&title);
+            self.window_title = title;
+        }
+        if self.recovery.is_none() && self.autosave.update(timing) {
+            self.autosave();
+        }
+        self.menu.on_mouse_move(mouse.xy);
+        let drawing_allowed = self.alert.is_none() && !self.menu.is_expanded();
+        let painting = if mouse.is_down(MouseButton::Left).is_some() {
+            Some(true)
+        } else if mouse.is_down(MouseButton::Right).is_some() {
+            Some(false)
+        } else {
+            None
+        };
+        if let Some(value) = painting.filter(|_| drawing_allowed) {
+            let paint = Paint {
+                layer: (held.contains(&KeyCode::ShiftLeft) || held.contains(&KeyCode::ShiftRight))
+                    .then_some(self.active_layer),
+                value,
+            };
+            self.pad_view.on_mouse_update(mouse.xy, paint);
             self.preview.update(&self.pad_view);
-            self.next_update.reset();
+        }
+        if mouse.is_down(MouseButton::Middle).is_some() {
+            if let Some(last) = self.pan_from {
+                self.pad_view.pan_by(mouse.xy - last);
+            }
+            self.pan_from = Some(mouse.xy);
+        } else {
+            self.pan_from = None;
         }
         self.result.clone()
     }
+
+    fn resuming(&mut self, result: Option<SceneResult>) {
+        self.result = Nothing;
+        let file_action = self.file_action.take();
+        match (result, file_action) {
+            (Some(SceneResult::SelectGlyph(chr)), _) => self.select_glyph(chr),
+            (Some(SceneResult::GuideLayers(layers, active)), _) => {
+                self.set_guide_layers(layers, active)
+            }
+            (Some(SceneResult::LoadFile(path) | SceneResult::SaveFile(path)), Some(action)) => {
+                self.on_file_chosen(action, path)
+            }
+            _ => {}
+        }
+    }
 }
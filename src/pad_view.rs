@@ -1,220 +1,789 @@
-use std::ptr::swap_nonoverlapping;
-
 use pixels_graphics_lib::buffer_graphics_lib::Graphics;
 use pixels_graphics_lib::prelude::{
-    fill, AppPrefs, Color, Coord, Rect, Shape, Timing, BLACK, DARK_GRAY, MID_GRAY, WHITE,
+    fill, stroke, Color, Coord, Rect, Shape, Timing, BLACK, CYAN, DARK_GRAY, GREEN, MAGENTA,
+    ORANGE, RED, WHITE, YELLOW,
 };
 use pixels_graphics_lib::ui::{PixelView, ViewState};
 use pixels_graphics_lib::MouseData;
 
-use crate::Settings;
+use crate::history::History;
+use fontpad::font::{Font, Metrics};
+use fontpad::glyph::{Anchor, Glyph, Region, ShiftOptions, MAX_SIZE};
+use fontpad::guide::GuideLayer;
+use fontpad::shape;
+use std::collections::BTreeMap;
 
 const PX_COLOR: Color = WHITE;
-const GUIDE_COLOR: Color = MID_GRAY;
 const LINE_COLOR: Color = DARK_GRAY;
+const SELECTION_COLOR: Color = CYAN;
+const BASELINE_COLOR: Color = RED;
+const DESCENT_COLOR: Color = ORANGE;
+const ASCENT_COLOR: Color = MAGENTA;
+const CAP_HEIGHT_COLOR: Color = GREEN;
+const X_HEIGHT_COLOR: Color = YELLOW;
+const HISTORY_LIMIT: usize = 100;
+const MIN_CELL_SIZE: usize = 2;
+const MAX_CELL_SIZE: usize = 40;
+/// Grid lines are hidden when cells are smaller than this
+const MIN_GRID_CELL_SIZE: usize = 4;
+/// Fraction of a cell covered by the marks of one guide layer
+const GUIDE_HEIGHT: f32 = 0.2;
+
+/// Colour guide marks on `layer` are drawn in
+pub fn layer_color(layer: &GuideLayer) -> Color {
+    let [r, g, b] = layer.color;
+    Color::new(r, g, b, 255)
+}
+
+/// What dragging on the pad does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tool {
+    /// Paint cells under the cursor
+    #[default]
+    Pencil,
+    /// Select a rectangle of cells, dragging inside the selection moves its contents
+    Select,
+    /// Drag from one end to the other
+    Line,
+    /// Drag from one corner to the opposite corner
+    Rect,
+    /// Drag from one corner of the bounding box to the opposite corner
+    Ellipse,
+    /// Set every connected cell that matches the clicked cell
+    Bucket,
+}
+
+/// How a stroke changes cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paint {
+    /// Guide layer to change, None changes dots
+    pub layer: Option<usize>,
+    /// Value cells are set to, false erases
+    pub value: bool,
+}
+
+impl Paint {
+    fn apply(&self, glyph: &mut Glyph, cells: &[(usize, usize)]) {
+        match self.layer {
+            Some(layer) => glyph.set_guides(layer, cells, self.value),
+            None => glyph.set_dots(cells, self.value),
+        }
+    }
+}
+
+/// Horizontal line drawn for a font metric
+#[derive(Debug, Clone, Copy)]
+pub struct MetricLine {
+    /// Rows from the top of the glyph to the line
+    pub y: isize,
+    pub color: Color,
+    /// The line is below the rows it measures (baseline and descent), rather than above them
+    pub under: bool,
+}
+
+/// Lines for every metric, the baseline is last so it's drawn on top
+pub fn metric_lines(metrics: &Metrics) -> [MetricLine; 5] {
+    let line = |y: isize, color: Color, under: bool| MetricLine { y, color, under };
+    let baseline = metrics.baseline as isize + 1;
+    [
+        line(metrics.row_for_height(metrics.ascent), ASCENT_COLOR, false),
+        line(
+            metrics.row_for_height(metrics.cap_height),
+            CAP_HEIGHT_COLOR,
+            false,
+        ),
+        line(
+            metrics.row_for_height(metrics.x_height),
+            X_HEIGHT_COLOR,
+            false,
+        ),
+        line(baseline + metrics.descent as isize, DESCENT_COLOR, true),
+        line(baseline, BASELINE_COLOR, true),
+    ]
+}
+
+/// State restored by undo and redo
+#[derive(Debug)]
+struct Snapshot {
+    /// Character of the glyph, restoring switches to it
+    chr: char,
+    glyph: Glyph,
+    /// Rest of the font, for edits that changed more than the glyph
+    font: Option<FontState>,
+}
+
+impl Snapshot {
+    fn glyph(chr: char, glyph: Glyph) -> Snapshot {
+        Snapshot {
+            chr,
+            glyph,
+            font: None,
+        }
+    }
+}
+
+/// Font wide part of a [Snapshot], only the glyphs changed by the edit are kept
+#[derive(Debug)]
+struct FontState {
+    size: (usize, usize),
+    metrics: Option<Metrics>,
+    guide_layers: Vec<GuideLayer>,
+    /// None if the font didn't have a glyph for the character
+    glyphs: BTreeMap<char, Option<Glyph>>,
+}
+
+impl FontState {
+    fn new<I: IntoIterator<Item = char>>(font: &Font, chars: I) -> FontState {
+        FontState {
+            size: font.size(),
+            metrics: font.metrics,
+            guide_layers: font.guide_layers.clone(),
+            glyphs: chars
+                .into_iter()
+                .map(|chr| (chr, font.glyphs.get(&chr).cloned()))
+                .collect(),
+        }
+    }
+
+    fn restore(self, font: &mut Font) {
+        (font.width, font.height) = self.size;
+        font.metrics = self.metrics;
+        font.guide_layers = self.guide_layers;
+        for (chr, glyph) in self.glyphs {
+            match glyph {
+                Some(glyph) => font.glyphs.insert(chr, glyph),
+                None => font.glyphs.remove(&chr),
+            };
+        }
+    }
+}
+
+/// Selected cells being dragged to a new position
+#[derive(Debug)]
+struct FloatingSelection {
+    /// Glyph with the selected cells cleared
+    base: Glyph,
+    contents: Glyph,
+    /// Selection when the drag started
+    origin: Region,
+    /// Cell the drag started from
+    grab: (usize, usize),
+}
 
 #[derive(Debug)]
 pub struct PadView {
     bounds: Rect,
-    pub dots: Vec<bool>,
-    pub guides: Vec<bool>,
-    pub size: (usize, usize),
-    last_cell_changed: usize,
+    /// Character the glyph is stored as in the font
+    chr: char,
+    glyph: Glyph,
+    /// Edits to every glyph, kept when switching glyph
+    history: History<Snapshot>,
+    /// State before the current mouse drag, used to make the drag one undo step
+    stroke_start: Option<Glyph>,
+    /// Cell painted by the last mouse update, the next update paints every cell between them
+    last_cell: Option<(usize, usize)>,
+    /// Cell size in px, if None cells are sized to fit the whole glyph
+    zoom: Option<usize>,
+    /// Offset of the drawing area from the center of the view
+    pan: Coord,
+    tool: Tool,
+    selection: Option<Region>,
+    /// Cell a new selection is being dragged from
+    select_from: Option<(usize, usize)>,
+    floating: Option<FloatingSelection>,
+    /// Cells copied or cut from a selection, kept when switching glyph
+    region_clipboard: Option<Glyph>,
+    /// Cell the shape being dragged started from
+    shape_from: Option<(usize, usize)>,
+    fill_shapes: bool,
+    /// Bucket fills spread to diagonal neighbours
+    fill_diagonal: bool,
+    /// Font metrics to draw, None if hidden
+    metrics: Option<Metrics>,
+    /// Layers of the glyph guides, hidden layers aren't drawn
+    guide_layers: Vec<GuideLayer>,
 }
 
 impl PadView {
-    pub fn new(pos: Coord, settings: &AppPrefs<Settings>) -> PadView {
+    pub fn new(pos: Coord, chr: char, glyph: &Glyph) -> PadView {
         PadView {
             bounds: Rect::new_with_size(pos, 240, 240),
-            size: (settings.data.width, settings.data.height),
-            dots: settings.data.dots.clone(),
-            guides: settings.data.guides.clone(),
-            last_cell_changed: usize::MAX,
+            chr,
+            glyph: glyph.clone(),
+            history: History::new(HISTORY_LIMIT),
+            stroke_start: None,
+            last_cell: None,
+            zoom: None,
+            pan: Coord::default(),
+            tool: Tool::default(),
+            selection: None,
+            select_from: None,
+            floating: None,
+            region_clipboard: None,
+            shape_from: None,
+            fill_shapes: false,
+            fill_diagonal: false,
+            metrics: None,
+            guide_layers: vec![],
         }
     }
 }
 
 impl PadView {
-    pub fn change_width(&mut self, value: isize) {
-        if value < 0 && self.size.0 > 1 {
-            self.size.0 -= 1;
+    /// Edit `glyph`, stored as `chr` in the font, undo history is kept
+    pub fn set_glyph(&mut self, chr: char, glyph: &Glyph) {
+        self.chr = chr;
+        self.glyph = glyph.clone();
+        self.stroke_start = None;
+        self.last_cell = None;
+        self.selection = None;
+        self.select_from = None;
+        self.floating = None;
+        self.clamp_pan();
+    }
+
+    pub fn glyph(&self) -> &Glyph {
+        &self.glyph
+    }
+
+    /// Character of the glyph being edited, can be changed by undo and redo
+    pub fn chr(&self) -> char {
+        self.chr
+    }
+
+    /// Forget every edit, for when the font is replaced or its guide layers change
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.glyph.size()
+    }
+
+    /// Run `edit` on the glyph and record the previous state in the history if anything changed
+    fn apply<F: FnOnce(&mut Glyph)>(&mut self, edit: F) {
+        let before = self.glyph.clone();
+        edit(&mut self.glyph);
+        if self.glyph != before {
+            self.history.record(Snapshot::glyph(self.chr, before));
+        }
+    }
+
+    /// Undo the last edit, switching to the glyph it was made on
+    ///
+    /// The glyph is stored in `font` first, and the font is restored if the edit changed more than the glyph
+    pub fn undo(&mut self, font: &mut Font) {
+        self.end_stroke();
+        font.set_glyph(self.chr, self.glyph.clone());
+        let Some(next) = self.history.peek_undo() else {
+            return;
+        };
+        let current = Self::snapshot(font, next);
+        if let Some(state) = self.history.undo(current) {
+            self.restore(state, font);
+        }
+    }
+
+    /// Redo the last undone edit, switching to the glyph it was made on
+    ///
+    /// The glyph is stored in `font` first, and the font is restored if the edit changed more than the glyph
+    pub fn redo(&mut self, font: &mut Font) {
+        self.end_stroke();
+        font.set_glyph(self.chr, self.glyph.clone());
+        let Some(next) = self.history.peek_redo() else {
+            return;
+        };
+        let current = Self::snapshot(font, next);
+        if let Some(state) = self.history.redo(current) {
+            self.restore(state, font);
+        }
+    }
+
+    /// State of `font` for the glyph and glyphs in `next`, the state about to be restored
+    fn snapshot(font: &Font, next: &Snapshot) -> Snapshot {
+        Snapshot {
+            chr: next.chr,
+            glyph: font.glyph_or_new(next.chr),
+            font: next
+                .font
+                .as_ref()
+                .map(|state| FontState::new(font, state.glyphs.keys().copied())),
+        }
+    }
+
+    fn restore(&mut self, state: Snapshot, font: &mut Font) {
+        if let Some(state_font) = state.font {
+            state_font.restore(font);
+        }
+        if state.chr == self.chr {
+            self.glyph = state.glyph;
+            self.clamp_selection();
+            self.clamp_pan();
+        } else {
+            self.set_glyph(state.chr, &state.glyph);
         }
-        if value > 0 && self.size.0 < 16 {
-            self.size.0 += 1;
+    }
+
+    pub fn change_width(&mut self, value: isize, anchor: Anchor) {
+        let mut width = self.glyph.width;
+        if value < 0 && width > 1 {
+            width -= 1;
+        }
+        if value > 0 && width < MAX_SIZE {
+            width += 1;
         }
-        self.dots = vec![false; self.size.0 * self.size.1];
-        self.guides = vec![false; self.size.0 * self.size.1];
+        self.apply(|glyph| glyph.resize(width, glyph.height, anchor));
+        self.clamp_selection();
+        self.clamp_pan();
     }
 
-    pub fn change_height(&mut self, value: isize) {
-        if value < 0 && self.size.1 > 1 {
-            self.size.1 -= 1;
+    pub fn change_height(&mut self, value: isize, anchor: Anchor) {
+        let mut height = self.glyph.height;
+        if value < 0 && height > 1 {
+            height -= 1;
         }
-        if value > 0 && self.size.1 < 16 {
-            self.size.1 += 1;
+        if value > 0 && height < MAX_SIZE {
+            height += 1;
+        }
+        self.apply(|glyph| glyph.resize(glyph.width, height, anchor));
+        self.clamp_selection();
+        self.clamp_pan();
+    }
+
+    /// Finish the current mouse drag, all changes made during it become a single undo step
+    pub fn end_stroke(&mut self) {
+        if let Some(before) = self.stroke_start.take() {
+            if self.glyph != before {
+                self.history.record(Snapshot::glyph(self.chr, before));
+            }
         }
-        self.dots = vec![false; self.size.0 * self.size.1];
-        self.guides = vec![false; self.size.0 * self.size.1];
+        self.last_cell = None;
+        self.select_from = None;
+        self.floating = None;
+        self.shape_from = None;
+    }
+
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.end_stroke();
+        self.tool = tool;
     }
 
-    pub fn on_mouse_update(&mut self, down_at: Coord, shift_down: bool) {
-        if let Some(cell) = self.cell_for(down_at) {
-            if shift_down {
-                if cell < self.guides.len() && self.last_cell_changed != cell {
-                    self.guides[cell] = !self.guides[cell];
-                    self.last_cell_changed = cell;
+    pub fn on_mouse_update(&mut self, down_at: Coord, paint: Paint) {
+        match self.tool {
+            Tool::Pencil => self.paint_cell(down_at, paint),
+            Tool::Select => {
+                if paint.value {
+                    self.drag_selection(down_at);
                 }
-            } else if cell < self.dots.len() && self.last_cell_changed != cell {
-                self.dots[cell] = !self.dots[cell];
-                self.last_cell_changed = cell;
             }
+            Tool::Line | Tool::Rect | Tool::Ellipse => self.drag_shape(down_at, paint),
+            Tool::Bucket => self.bucket_fill(down_at, paint),
         }
     }
 
-    pub fn copy_str(&self) -> String {
-        let mut output = String::new();
-        let mut i = 1;
-        for value in &self.dots {
-            output.push_str(if *value { "true" } else { "false" });
-            output.push(',');
-            if i >= self.size.0 {
-                output.push('\n');
-                i = 0;
+    pub fn fill_diagonal(&self) -> bool {
+        self.fill_diagonal
+    }
+
+    pub fn set_fill_diagonal(&mut self, diagonal: bool) {
+        self.fill_diagonal = diagonal;
+    }
+
+    /// Fill the area under `pos` once per stroke
+    fn bucket_fill(&mut self, pos: Coord, paint: Paint) {
+        if self.stroke_start.is_some() {
+            return;
+        }
+        let Some(cell) = self.cell_xy_for(pos, false) else {
+            return;
+        };
+        self.stroke_start = Some(self.glyph.clone());
+        let layer = match paint.layer {
+            Some(layer) => &self.glyph.guides[layer],
+            None => &self.glyph.dots,
+        };
+        let cells = shape::flood(layer, self.glyph.width, cell, self.fill_diagonal);
+        paint.apply(&mut self.glyph, &cells);
+    }
+
+    pub fn fill_shapes(&self) -> bool {
+        self.fill_shapes
+    }
+
+    pub fn set_fill_shapes(&mut self, filled: bool) {
+        self.fill_shapes = filled;
+    }
+
+    /// Redraw the shape from where the drag started to `pos` on top of the glyph from before the drag
+    fn drag_shape(&mut self, pos: Coord, paint: Paint) {
+        if self.stroke_start.is_none() {
+            let Some(cell) = self.cell_xy_for(pos, false) else {
+                return;
+            };
+            self.stroke_start = Some(self.glyph.clone());
+            self.shape_from = Some(cell);
+        }
+        let (Some(before), Some(from), Some(to)) = (
+            &self.stroke_start,
+            self.shape_from,
+            self.cell_xy_for(pos, true),
+        ) else {
+            return;
+        };
+        let region = Region::from_corners(from, to);
+        let cells = match self.tool {
+            Tool::Line => shape::line(from, to),
+            Tool::Rect => shape::rect(region, self.fill_shapes),
+            Tool::Ellipse => shape::ellipse(region, self.fill_shapes),
+            Tool::Pencil | Tool::Select | Tool::Bucket => return,
+        };
+        let mut glyph = before.clone();
+        paint.apply(&mut glyph, &cells);
+        self.glyph = glyph;
+    }
+
+    /// Paint the cell under `pos` and every cell on the line from the previously painted cell
+    fn paint_cell(&mut self, pos: Coord, paint: Paint) {
+        let cell = self.cell_xy_for(pos, false);
+        if let Some(cell) = cell {
+            if self.stroke_start.is_none() {
+                self.stroke_start = Some(self.glyph.clone());
             }
-            i += 1;
+            let cells = match self.last_cell {
+                Some(last) => shape::line(last, cell),
+                None => vec![cell],
+            };
+            paint.apply(&mut self.glyph, &cells);
         }
-        output.trim().to_string()
+        self.last_cell = cell;
     }
 
-    pub fn paste_str(&mut self, value: &str) {
-        let parts: Vec<&str> = value.split(',').collect();
-        if parts.len() != self.dots.len() {
-            eprintln!(
-                "Invalid length, expected {} found {}",
-                self.dots.len(),
-                parts.len()
-            );
+    /// Start a new selection, or move the current one if the drag started inside it
+    fn drag_selection(&mut self, pos: Coord) {
+        if self.stroke_start.is_none() {
+            let Some(cell) = self.cell_xy_for(pos, false) else {
+                return;
+            };
+            self.stroke_start = Some(self.glyph.clone());
+            match self.selection {
+                Some(region) if region.contains(cell.0, cell.1) => {
+                    let mut base = self.glyph.clone();
+                    base.clear_region(region);
+                    self.floating = Some(FloatingSelection {
+                        base,
+                        contents: self.glyph.copy_region(region),
+                        origin: region,
+                        grab: cell,
+                    });
+                }
+                _ => {
+                    self.select_from = Some(cell);
+                    self.selection = Some(Region::from_corners(cell, cell));
+                }
+            }
             return;
         }
-        if !parts.iter().all(|s| matches!(s.trim(), "true" | "false")) {
-            eprintln!("Invalid string pasted must be ([true|false],){{16,576}}");
+        let Some(cell) = self.cell_xy_for(pos, true) else {
             return;
+        };
+        if let Some(floating) = &self.floating {
+            let region = floating.origin;
+            let x = (region.x + cell.0)
+                .saturating_sub(floating.grab.0)
+                .min(self.glyph.width - region.width);
+            let y = (region.y + cell.1)
+                .saturating_sub(floating.grab.1)
+                .min(self.glyph.height - region.height);
+            let mut glyph = floating.base.clone();
+            glyph.paste(&floating.contents, x as isize, y as isize);
+            self.glyph = glyph;
+            self.selection = Some(Region::new(x, y, region.width, region.height));
+        } else if let Some(from) = self.select_from {
+            self.selection = Some(Region::from_corners(from, cell));
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        self.end_stroke();
+        self.selection = Some(self.glyph.bounds());
+    }
+
+    /// Returns false if nothing was selected
+    pub fn deselect(&mut self) -> bool {
+        self.end_stroke();
+        self.selection.take().is_some()
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selection = self
+            .selection
+            .and_then(|region| region.clamp_to(self.glyph.size()));
+    }
+
+    /// Copy the selected cells to the internal clipboard, returns false if nothing is selected
+    pub fn copy_selection(&mut self) -> bool {
+        match self.selection {
+            Some(region) => {
+                self.region_clipboard = Some(self.glyph.copy_region(region));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copy the selected cells to the internal clipboard and clear them, returns false if nothing is selected
+    pub fn cut_selection(&mut self) -> bool {
+        if !self.copy_selection() {
+            return false;
+        }
+        if let Some(region) = self.selection {
+            self.apply(|glyph| glyph.clear_region(region));
+        }
+        true
+    }
+
+    pub fn clear_region_clipboard(&mut self) {
+        self.region_clipboard = None;
+    }
+
+    /// Paste cells from the internal clipboard with the top left at the cell under `pos`
+    /// (or the selection if the mouse isn't over the glyph), the pasted cells become the selection
+    ///
+    /// Returns false if the internal clipboard is empty
+    pub fn paste_region(&mut self, pos: Coord) -> bool {
+        let Some(contents) = self.region_clipboard.clone() else {
+            return false;
+        };
+        self.end_stroke();
+        let (x, y) = self
+            .cell_xy_for(pos, false)
+            .or_else(|| self.selection.map(|region| (region.x, region.y)))
+            .unwrap_or_default();
+        self.apply(|glyph| glyph.paste(&contents, x as isize, y as isize));
+        self.selection =
+            Region::new(x, y, contents.width, contents.height).clamp_to(self.glyph.size());
+        true
+    }
+
+    /// Run `edit` on the selection, or the whole glyph if nothing is selected
+    fn apply_selected<F: FnOnce(&mut Glyph)>(&mut self, edit: F) {
+        match self.selection {
+            Some(region) => self.apply(|glyph| glyph.edit_region(region, edit)),
+            None => self.apply(edit),
+        }
+    }
+
+    pub fn paste_str(&mut self, value: &str) {
+        let mut glyph = self.glyph.clone();
+        match glyph.paste_str(value) {
+            Ok(()) => self.apply(|current| *current = glyph),
+            Err(err) => eprintln!("{err}"),
         }
-        parts
-            .iter()
-            .enumerate()
-            .for_each(|(i, &value)| self.dots[i] = value.trim() == "true")
     }
 
     pub fn clear(&mut self) {
-        self.dots.fill(false);
+        self.apply(Glyph::clear);
     }
 
     pub fn clear_guides(&mut self) {
-        self.guides.fill(false);
+        self.apply(Glyph::clear_guides);
     }
 
     pub fn fill(&mut self) {
-        self.dots.fill(true);
+        self.apply(Glyph::fill);
     }
 
-    pub fn flip_h(&mut self) {
-        self.dots = Self::horz_swapper(self.size.0, self.size.1, &self.dots);
+    pub fn invert(&mut self) {
+        self.apply_selected(Glyph::invert);
     }
 
-    pub fn flip_v(&mut self) {
-        self.dots = Self::vert_swapper(self.size.0, self.size.1, &self.dots);
-    }
-
-    fn horz_swapper(width: usize, height: usize, dots: &[bool]) -> Vec<bool> {
-        let mut output = dots.to_vec();
-        let half_width = (width as f32 / 2.).floor() as usize;
-        for y in 0..height {
-            for x in 0..half_width {
-                let target_right_i = (width - 1 - x) + y * width;
-                let target_left_i = x + y * width;
-                unsafe {
-                    swap_nonoverlapping(&mut output[target_left_i], &mut output[target_right_i], 1);
-                }
-            }
-        }
-        output
+    pub fn rotate_cw(&mut self) {
+        self.transform(Glyph::rotate_cw);
     }
 
-    fn vert_swapper(width: usize, height: usize, dots: &[bool]) -> Vec<bool> {
-        let mut output = dots.to_vec();
-        let half_height = (height as f32 / 2.).floor() as usize;
-        for y in 0..half_height {
-            unsafe {
-                swap_nonoverlapping(
-                    &mut output[y * width],
-                    &mut output[(height - 1 - y) * width],
-                    width,
-                );
-            }
+    pub fn rotate_ccw(&mut self) {
+        self.transform(Glyph::rotate_ccw);
+    }
+
+    pub fn transpose(&mut self) {
+        self.transform(Glyph::transpose);
+    }
+
+    /// Apply a transform that can change the glyph size, the selection is removed
+    pub fn transform<F: FnOnce(&mut Glyph)>(&mut self, edit: F) {
+        self.end_stroke();
+        self.selection = None;
+        self.apply(edit);
+        self.clamp_pan();
+    }
+
+    /// Apply `edit` to `font`, with the glyph stored in it first, undone as a single step
+    ///
+    /// The glyph is reloaded from the font afterwards and the selection is removed
+    pub fn transform_font<F: FnOnce(&mut Font)>(&mut self, font: &mut Font, edit: F) {
+        self.end_stroke();
+        self.selection = None;
+        let before_glyph = self.glyph.clone();
+        font.set_glyph(self.chr, before_glyph.clone());
+        let before = font.clone();
+        edit(font);
+        self.glyph = font.glyph_or_new(self.chr);
+        if *font != before {
+            let changed = before
+                .glyphs
+                .keys()
+                .chain(font.glyphs.keys())
+                .copied()
+                .filter(|chr| before.glyphs.get(chr) != font.glyphs.get(chr));
+            self.history.record(Snapshot {
+                chr: self.chr,
+                glyph: before_glyph,
+                font: Some(FontState::new(&before, changed)),
+            });
         }
-        output
+        self.clamp_pan();
     }
 
-    fn cell_for(&self, pos: Coord) -> Option<usize> {
+    pub fn shear(&mut self, rows_per_cell: usize) {
+        self.apply_selected(|glyph| glyph.shear(rows_per_cell));
+    }
+
+    pub fn flip_h(&mut self) {
+        self.apply_selected(Glyph::flip_h);
+    }
+
+    pub fn flip_v(&mut self) {
+        self.apply_selected(Glyph::flip_v);
+    }
+
+    /// Cell under `pos`, if `clamp` is true positions outside the glyph return the nearest cell
+    fn cell_xy_for(&self, pos: Coord, clamp: bool) -> Option<(usize, usize)> {
         let area = self.drawing_area();
-        let cell_size = self.square_size();
-        if area.contains(pos) {
-            let moved = pos - area.top_left();
-            let x = moved.x as usize / cell_size;
-            let y = moved.y as usize / cell_size;
-            return Some(x + y * self.size.0);
+        let cell_size = self.square_size() as isize;
+        let moved = pos - area.top_left();
+        let x = moved.x.div_euclid(cell_size);
+        let y = moved.y.div_euclid(cell_size);
+        if clamp {
+            return Some((
+                x.clamp(0, self.glyph.width as isize - 1) as usize,
+                y.clamp(0, self.glyph.height as isize - 1) as usize,
+            ));
+        }
+        let inside = (0..self.glyph.width as isize).contains(&x)
+            && (0..self.glyph.height as isize).contains(&y);
+        if self.bounds.contains(pos) && inside {
+            Some((x as usize, y as usize))
+        } else {
+            None
         }
-        None
     }
 
-    fn square_size(&self) -> usize {
-        let size = self.size.0.max(self.size.1);
+    /// Cell size that fits the whole glyph in the view
+    fn fit_size(&self) -> usize {
+        let size = self.glyph.width.max(self.glyph.height);
         let area = ((self.bounds.width().min(self.bounds.height()) as f32) * 0.98).round() as usize;
-        (area / size).min(20)
+        (area / size).clamp(MIN_CELL_SIZE, 20)
+    }
+
+    /// Height of a guide mark, marks are stacked from the bottom of a cell and shrink if every visible layer wouldn't fit
+    fn guide_height(&self) -> f32 {
+        let visible = self
+            .guide_layers
+            .iter()
+            .filter(|layer| layer.visible)
+            .count();
+        self.square_size() as f32 * GUIDE_HEIGHT.min(1. / visible.max(1) as f32)
+    }
+
+    fn square_size(&self) -> usize {
+        self.zoom.unwrap_or_else(|| self.fit_size())
     }
 
     fn drawing_area(&self) -> Rect {
-        let square_size = self.square_size();
-        let drawing_area =
-            Rect::new_with_size((0, 0), square_size * self.size.0, square_size * self.size.1);
+        self.centered_area(self.square_size())
+            .translate_by(self.pan)
+    }
+
+    fn centered_area(&self, square_size: usize) -> Rect {
+        let drawing_area = Rect::new_with_size(
+            (0, 0),
+            square_size * self.glyph.width,
+            square_size * self.glyph.height,
+        );
         drawing_area.move_center_to(self.bounds.center())
     }
 
-    pub fn move_up(&mut self) {
-        let mut removed = vec![];
-        for _ in 0..self.size.0 {
-            removed.push(self.dots.remove(0));
+    /// Zoom in (positive) or out (negative) keeping the cell under `pos` in place
+    ///
+    /// Larger cells zoom in bigger steps
+    pub fn zoom_by(&mut self, amount: isize, pos: Coord) {
+        let old_size = self.square_size();
+        let step = (old_size / 4).max(1) as isize;
+        let new_size = old_size
+            .saturating_add_signed(amount * step)
+            .clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        if new_size == old_size {
+            return;
         }
-        self.dots.extend_from_slice(&removed);
+        let pos = if self.bounds.contains(pos) {
+            pos
+        } else {
+            self.bounds.center()
+        };
+        let area = self.drawing_area();
+        let old_offset = pos - area.top_left();
+        let new_offset = old_offset * new_size as isize / old_size as isize;
+        let new_top_left = pos - new_offset;
+        self.zoom = Some(new_size);
+        self.pan = new_top_left - self.centered_area(new_size).top_left();
+        self.clamp_pan();
     }
 
-    pub fn move_down(&mut self) {
-        let mut removed = vec![];
-        for _ in 0..self.size.0 {
-            removed.push(self.dots.remove(self.dots.len() - 1));
-        }
-        for value in removed.into_iter() {
-            self.dots.insert(0, value);
-        }
+    /// Return to showing the whole glyph
+    pub fn reset_zoom(&mut self) {
+        self.zoom = None;
+        self.pan = Coord::default();
     }
 
-    pub fn move_left(&mut self) {
-        for i in 0..self.size.1 {
-            let take = i * self.size.0;
-            let insert = take + (self.size.0 - 1);
-            let value = self.dots.remove(take);
-            self.dots.insert(insert, value);
-        }
+    pub fn pan_by(&mut self, diff: Coord) {
+        self.pan = self.pan + diff;
+        self.clamp_pan();
     }
 
-    pub fn move_right(&mut self) {
-        for i in 0..self.size.1 {
-            let insert = i * self.size.0;
-            let take = insert + (self.size.0 - 1);
-            let value = self.dots.remove(take);
-            self.dots.insert(insert, value);
-        }
+    /// Stop the drawing area from being moved further than needed to see its edges
+    fn clamp_pan(&mut self) {
+        let area = self.centered_area(self.square_size());
+        let max_x = (area.width() as isize - self.bounds.width() as isize).max(0) / 2;
+        let max_y = (area.height() as isize - self.bounds.height() as isize).max(0) / 2;
+        self.pan = Coord::new(
+            self.pan.x.clamp(-max_x - 1, max_x + 1),
+            self.pan.y.clamp(-max_y - 1, max_y + 1),
+        );
+    }
+
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.metrics
+    }
+
+    pub fn set_metrics(&mut self, metrics: Option<Metrics>) {
+        self.metrics = metrics;
+    }
+
+    pub fn guide_layers(&self) -> &[GuideLayer] {
+        &self.guide_layers
+    }
+
+    pub fn set_guide_layers(&mut self, layers: &[GuideLayer]) {
+        self.guide_layers = layers.to_vec();
+    }
+
+    /// Row of the cell under `pos`
+    pub fn row_at(&self, pos: Coord) -> Option<usize> {
+        self.cell_xy_for(pos, false).map(|(_, y)| y)
+    }
+
+    pub fn shift(&mut self, x: isize, y: isize, options: ShiftOptions) {
+        self.apply_selected(|glyph| glyph.shift(x, y, options));
     }
 }
 
@@ -234,36 +803,69 @@ impl PixelView for PadView {
 
         let size = self.square_size();
         let area = self.drawing_area();
+        let guide_height = self.guide_height();
 
-        for x in 0..self.size.0 {
-            for y in 0..self.size.1 {
-                let i = x + y * self.size.0;
+        for x in 0..self.glyph.width {
+            for y in 0..self.glyph.height {
+                let i = x + y * self.glyph.width;
                 let cell = Rect::new_with_size(area.top_left() + (x * size, y * size), size, size);
-                if self.dots[i] {
+                if self.glyph.dots[i] {
                     graphics.draw_rect(cell.clone(), fill(PX_COLOR));
                 }
-                if self.guides[i] {
+                let marks = self
+                    .guide_layers
+                    .iter()
+                    .zip(&self.glyph.guides)
+                    .filter(|(layer, guides)| layer.visible && guides[i]);
+                for (row, (layer, _)) in marks.enumerate() {
+                    let top = guide_height * (row + 1) as f32;
                     let guide_cell = Rect::new(
-                        cell.bottom_left() - (0., size as f32 * 0.2),
-                        cell.bottom_right(),
+                        cell.bottom_left() - (0., top),
+                        cell.bottom_right() - (0., top - guide_height),
                     );
-                    graphics.draw_rect(guide_cell, fill(GUIDE_COLOR));
+                    graphics.draw_rect(guide_cell, fill(layer_color(layer)));
                 }
             }
         }
 
-        for x in 0..=self.size.0 {
-            graphics.draw_line(
-                area.top_left() + (x * size, 0),
-                area.bottom_left() + (x * size, 0),
-                LINE_COLOR,
-            );
+        if size >= MIN_GRID_CELL_SIZE {
+            for x in 0..=self.glyph.width {
+                graphics.draw_line(
+                    area.top_left() + (x * size, 0),
+                    area.bottom_left() + (x * size, 0),
+                    LINE_COLOR,
+                );
+            }
+            for y in 0..=self.glyph.height {
+                graphics.draw_line(
+                    area.top_left() + (0, y * size),
+                    area.top_right() + (0, y * size),
+                    LINE_COLOR,
+                );
+            }
         }
-        for y in 0..=self.size.1 {
-            graphics.draw_line(
-                area.top_left() + (0, y * size),
-                area.top_right() + (0, y * size),
-                LINE_COLOR,
+
+        if let Some(metrics) = &self.metrics {
+            for line in metric_lines(metrics) {
+                if (0..=self.glyph.height as isize).contains(&line.y) {
+                    let y = line.y * size as isize;
+                    graphics.draw_line(
+                        area.top_left() + (0, y),
+                        area.top_right() + (0, y),
+                        line.color,
+                    );
+                }
+            }
+        }
+
+        if let Some(region) = self.selection {
+            graphics.draw_rect(
+                Rect::new_with_size(
+                    area.top_left() + (region.x * size, region.y * size),
+                    region.width * size,
+                    region.height * size,
+                ),
+                stroke(SELECTION_COLOR),
             );
         }
 
@@ -280,3 +882,103 @@ impl PixelView for PadView {
         ViewState::Normal
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_transform_is_one_undo_step() {
+        let mut font = Font::new(2, 2);
+        let mut view = PadView::new(Coord::default(), 'A', &font.glyph_or_new('A'));
+        view.fill();
+        view.transform_font(&mut font, |font| {
+            font.glyphs.values_mut().for_each(Glyph::invert)
+        });
+        assert!(view.glyph().is_empty());
+        assert!(font.glyphs[&'B'].dots.iter().all(|&dot| dot));
+
+        view.undo(&mut font);
+        assert!(view.glyph().dots.iter().all(|&dot| dot));
+        assert!(font.glyphs[&'B'].is_empty());
+
+        view.redo(&mut font);
+        assert!(view.glyph().is_empty());
+        assert!(font.glyphs[&'B'].dots.iter().all(|&dot| dot));
+
+        view.undo(&mut font);
+        view.undo(&mut font);
+        assert!(view.glyph().is_empty());
+        assert!(font.glyphs[&'B'].is_empty());
+    }
+
+    #[test]
+    fn font_resize_undoes_default_size() {
+        let mut font = Font::new(2, 2);
+        let mut view = PadView::new(Coord::default(), 'A', &font.glyph_or_new('A'));
+        view.transform_font(&mut font, |font| font.resize_by(1, 0, Anchor::TopLeft));
+        assert_eq!(view.size(), (3, 2));
+        assert_eq!(font.size(), (3, 2));
+
+        view.undo(&mut font);
+        assert_eq!(view.size(), (2, 2));
+        assert_eq!(font.size(), (2, 2));
+        assert_eq!(font.glyphs[&'B'].size(), (2, 2));
+    }
+
+    #[test]
+    fn history_is_kept_when_switching_glyph() {
+        let mut font = Font::new(2, 2);
+        let mut view = PadView::new(Coord::default(), 'A', &font.glyph_or_new('A'));
+        view.fill();
+        font.set_glyph('A', view.glyph().clone());
+        view.set_glyph('B', &font.glyph_or_new('B'));
+        view.invert();
+
+        view.undo(&mut font);
+        assert_eq!(view.chr(), 'B');
+        assert!(view.glyph().is_empty());
+        view.undo(&mut font);
+        assert_eq!(view.chr(), 'A');
+        assert!(view.glyph().is_empty());
+        assert!(font.glyphs[&'B'].is_empty());
+
+        view.redo(&mut font);
+        assert!(view.glyph().dots.iter().all(|&dot| dot));
+        view.redo(&mut font);
+        assert_eq!(view.chr(), 'B');
+        assert!(view.glyph().dots.iter().all(|&dot| dot));
+        assert!(font.glyphs[&'A'].dots.iter().all(|&dot| dot));
+    }
+
+    #[test]
+    fn font_transform_only_keeps_changed_glyphs() {
+        let mut font = Font::new(2, 2);
+        let mut view = PadView::new(Coord::default(), 'A', &font.glyph_or_new('A'));
+        view.transform_font(&mut font, |font| {
+            font.glyphs.get_mut(&'B').unwrap().invert()
+        });
+        let state = view
+            .history
+            .peek_undo()
+            .and_then(|state| state.font.as_ref());
+        assert_eq!(
+            state.map(|state| state.glyphs.keys().collect::<Vec<_>>()),
+            Some(vec![&'B'])
+        );
+    }
+
+    #[test]
+    fn guide_marks_fit_in_cell() {
+        let mut view = PadView::new(Coord::default(), 'A', &Glyph::new(4, 4));
+        let size = view.square_size() as f32;
+        let layer = GuideLayer::new("Guides", [0, 0, 0]);
+        view.set_guide_layers(&vec![layer.clone(); 2]);
+        assert_eq!(view.guide_height(), size * GUIDE_HEIGHT);
+
+        let mut layers = vec![layer; 8];
+        layers[0].visible = false;
+        view.set_guide_layers(&layers);
+        assert!(view.guide_height() * 7. <= size);
+    }
+}
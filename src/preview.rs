@@ -1,46 +1,98 @@
 use pixels_graphics_lib::buffer_graphics_lib::Graphics;
 use pixels_graphics_lib::prelude::{
-    AppPrefs, Color, Coord, Rect, Scaling, Shape, Timing, BLACK, MID_GRAY, WHITE,
+    Color, Coord, Image, Rect, Scaling, Shape, Timing, BLACK, WHITE,
 };
 use pixels_graphics_lib::ui::{PixelView, ViewState};
 use pixels_graphics_lib::MouseData;
 
-use crate::pad_view::PadView;
-use crate::Settings;
+use crate::pad_view::{layer_color, metric_lines, PadView};
+use fontpad::font::Metrics;
+use fontpad::glyph::Glyph;
 
 const PX_COLOR: Color = WHITE;
-const GUIDE_COLOR: Color = MID_GRAY.with_alpha(128);
+const GUIDE_ALPHA: u8 = 128;
+const METRIC_ALPHA: u8 = 160;
+/// Pixels metric lines extend past the glyph on each side
+const METRIC_OVERHANG: isize = 2;
 
 #[derive(Debug)]
 pub struct Preview {
     bounds: Rect,
     size: (usize, usize),
     dots: Vec<bool>,
-    guides: Vec<bool>,
+    /// Colour and marks of each visible guide layer
+    guides: Vec<(Color, Vec<bool>)>,
     history: Vec<Vec<bool>>,
+    metrics: Option<Metrics>,
 }
 
 impl Preview {
-    pub fn new(pos: Coord, settings: &AppPrefs<Settings>) -> Preview {
+    pub fn new(pos: Coord, glyph: &Glyph) -> Preview {
         Preview {
             bounds: Rect::new_with_size(pos, 56, 100),
-            size: (settings.data.width, settings.data.height),
-            dots: settings.data.dots.clone(),
-            guides: settings.data.guides.clone(),
+            size: (glyph.width, glyph.height),
+            dots: glyph.dots.clone(),
+            guides: vec![],
             history: vec![],
+            metrics: None,
         }
     }
 }
 
 impl Preview {
+    /// Render `dots` at 1x, set pixels are white and the rest transparent
+    pub fn create_image(size: (usize, usize), dots: &[bool]) -> Image {
+        let mut image_buffer = Graphics::create_buffer_u8(size.0, size.1);
+        let mut image_graphics = Graphics::new_u8_rgba(&mut image_buffer, size.0, size.1)
+            .expect("Creating preview buffer");
+
+        for x in 0..size.0 {
+            for y in 0..size.1 {
+                let i = x + y * size.0;
+                if dots[i] {
+                    image_graphics.set_pixel(x as isize, y as isize, PX_COLOR);
+                }
+            }
+        }
+
+        image_graphics.copy_to_image()
+    }
+
     pub fn update(&mut self, pad_view: &PadView) {
-        if self.size != pad_view.size {
+        let glyph = pad_view.glyph();
+        if self.size != glyph.size() {
             println!("history cleared");
             self.history.clear();
         }
-        self.guides = pad_view.guides.clone();
-        self.dots = pad_view.dots.clone();
-        self.size = pad_view.size;
+        self.guides = pad_view
+            .guide_layers()
+            .iter()
+            .zip(&glyph.guides)
+            .filter(|(layer, _)| layer.visible)
+            .map(|(layer, guides)| (layer_color(layer).with_alpha(GUIDE_ALPHA), guides.clone()))
+            .collect();
+        self.dots = glyph.dots.clone();
+        self.size = glyph.size();
+        self.metrics = pad_view.metrics();
+    }
+
+    /// Draw metric lines for the glyph drawn at `pos` and `scale`, each line is on the pixel row next to the rows it measures
+    fn draw_metrics(&self, graphics: &mut Graphics, pos: Coord, scale: usize) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let width = (self.size.0 * scale) as isize;
+        for line in metric_lines(metrics) {
+            if !(0..=self.size.1 as isize).contains(&line.y) {
+                continue;
+            }
+            let y = pos.y + line.y * scale as isize - if line.under { 0 } else { 1 };
+            graphics.draw_line(
+                (pos.x - METRIC_OVERHANG, y),
+                (pos.x + width - 1 + METRIC_OVERHANG, y),
+                line.color.with_alpha(METRIC_ALPHA),
+            );
+        }
     }
 
     pub fn add_to_history(&mut self) {
@@ -71,37 +123,43 @@ impl PixelView for Preview {
 
         graphics.clear_aware(BLACK);
 
-        let mut image_buffer = Graphics::create_buffer_u8(self.size.0, self.size.1);
-        let mut image_graphics = Graphics::new_u8_rgba(&mut image_buffer, self.size.0, self.size.1)
-            .expect("Creating preview buffer");
-
-        for x in 0..self.size.0 {
-            for y in 0..self.size.1 {
-                let i = x + y * self.size.0;
-                if self.dots[i] {
-                    image_graphics.set_pixel(x as isize, y as isize, WHITE);
-                }
-            }
-        }
-
-        let offset = self.bounds.top_left() + ((self.bounds.width() / 2) - (self.size.0 / 2), 2);
-        let image = image_graphics.copy_to_image();
+        let image = Self::create_image(self.size, &self.dots);
+        let center_x = (self.bounds.width() / 2) as isize;
+        let offset = self.bounds.top_left() + (center_x - (self.size.0 / 2) as isize, 2);
 
+        self.draw_metrics(graphics, offset, 1);
         graphics.draw_image(offset, &image);
 
-        let offset = self.bounds.top_left() + ((self.bounds.width() / 2) - self.size.0 + 1, 32);
+        //large glyphs are only shown at 1x
+        let scaled_y = 32.max(self.size.1 + 6);
+        if self.size.0 * 2 <= self.bounds.width()
+            && scaled_y + self.size.1 * 2 < self.bounds.height().saturating_sub(self.size.1)
+        {
+            let offset =
+                self.bounds.top_left() + (center_x - self.size.0 as isize + 1, scaled_y as isize);
 
-        let scaled = image.scale(Scaling::nn_double());
+            let scaled = image.scale(Scaling::nn_double());
 
-        graphics.draw_image(offset, &scaled);
+            self.draw_metrics(graphics, offset, 2);
+            graphics.draw_image(offset, &scaled);
+        }
 
+        //history is skipped if it would overlap the 1x glyph
+        let history_y = self
+            .bounds
+            .height()
+            .checked_sub(self.size.1 + 1)
+            .filter(|&y| y > self.size.1 + 2);
+        let Some(y) = history_y else {
+            graphics.clip_mut().set_all_valid();
+            return;
+        };
         let history_width = ((self.size.0) * self.history.len()) as isize;
         let start_x = if history_width > self.bounds.width() as isize {
             self.bounds.width() as isize - history_width
         } else {
             self.bounds.center().x - (history_width / 2)
         };
-        let y = self.bounds.height() - self.size.1 - 1;
         for (i, dots) in self.history.iter().enumerate() {
             let start = self.bounds.top_left() + (start_x + (self.size.0 * i) as isize, y as isize);
             for x in 0..self.size.0 {
@@ -110,8 +168,10 @@ impl PixelView for Preview {
                     if dots[px] {
                         graphics.set_pixel(start.x + x as isize, start.y + y as isize, PX_COLOR);
                     }
-                    if self.guides[px] {
-                        graphics.set_pixel(start.x + x as isize, start.y + y as isize, GUIDE_COLOR);
+                    for (color, guides) in &self.guides {
+                        if guides[px] {
+                            graphics.set_pixel(start.x + x as isize, start.y + y as isize, *color);
+                        }
                     }
                 }
             }
@@ -130,3 +190,22 @@ impl PixelView for Preview {
         ViewState::Normal
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_is_skipped_for_tall_glyphs() {
+        let mut glyph = Glyph::new(8, 64);
+        glyph.dots.fill(true);
+        let mut preview = Preview::new(Coord::default(), &glyph);
+        preview.add_to_history();
+        preview.dots.fill(false);
+        let (width, height) = (preview.bounds.width(), preview.bounds.height());
+        let mut buffer = Graphics::create_buffer_u8(width, height);
+        let mut graphics = Graphics::new_u8_rgba(&mut buffer, width, height).unwrap();
+        preview.render(&mut graphics, &MouseData::default());
+        assert!(buffer.chunks(4).all(|px| px != [255, 255, 255, 255]));
+    }
+}
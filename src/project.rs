@@ -0,0 +1,284 @@
+use crate::font::Font;
+use crate::glyph::{Glyph, MAX_SIZE};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// File extension for font projects
+pub const EXTENSION: &str = "fontpad";
+/// Version written to new project files
+///
+/// 0 is a [Font] without a version, written by fontpad before projects were versioned,
+/// 1 has a single list of guides per glyph instead of one per guide layer
+pub const VERSION: u64 = 2;
+/// Character the glyph from [LegacyGlyph] is stored as
+pub const LEGACY_CHAR: char = 'A';
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProjectFile<F> {
+    version: u64,
+    font: F,
+}
+
+/// Settings layout from before fonts had more than one glyph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegacyGlyph {
+    pub width: usize,
+    pub height: usize,
+    pub dots: Vec<bool>,
+    #[serde(default)]
+    pub guides: Vec<bool>,
+}
+
+impl LegacyGlyph {
+    /// New font at the size of the glyph, with the glyph at [LEGACY_CHAR]
+    pub fn into_font(self) -> Font {
+        let mut font = Font::new(self.width, self.height);
+        let guides = if self.guides.is_empty() {
+            vec![false; self.dots.len()]
+        } else {
+            self.guides
+        };
+        font.set_glyph(
+            LEGACY_CHAR,
+            Glyph {
+                width: self.width,
+                height: self.height,
+                dots: self.dots,
+                guides: vec![guides],
+            },
+        );
+        font
+    }
+}
+
+/// Convert `font` to JSON, guides are kept
+pub fn write(font: &Font) -> Result<String> {
+    serde_json::to_string_pretty(&ProjectFile {
+        version: VERSION,
+        font,
+    })
+    .context("Unable to serialize project")
+}
+
+/// Parse a project of any version, older versions are migrated and the font is validated
+pub fn parse(text: &str) -> Result<Font> {
+    let value: Value = serde_json::from_str(text).context("Project is not valid JSON")?;
+    let font = migrate(value)?;
+    validate(&font)?;
+    Ok(font)
+}
+
+/// Convert any project layout (or [LegacyGlyph]) to a [Font], the font isn't validated
+pub fn migrate(value: Value) -> Result<Font> {
+    if let Some(version) = value.get("version") {
+        let Some(version) = version.as_u64() else {
+            bail!("Project version must be a number, found {version}");
+        };
+        if version > VERSION {
+            bail!("Project is version {version}, this version of fontpad supports up to {VERSION}");
+        }
+        let file: ProjectFile<Font> =
+            serde_json::from_value(value).context("Invalid project file")?;
+        Ok(file.font)
+    } else if value.get("glyphs").is_some() {
+        serde_json::from_value(value).context("Invalid unversioned project file")
+    } else if value.get("dots").is_some() {
+        let glyph: LegacyGlyph =
+            serde_json::from_value(value).context("Invalid single glyph project")?;
+        Ok(glyph.into_font())
+    } else {
+        bail!("Not a fontpad project, missing version");
+    }
+}
+
+/// Check every glyph has a valid size (up to [MAX_SIZE]) and a value for every dot and guide,
+/// and that metrics fit in the font height
+pub fn validate(font: &Font) -> Result<()> {
+    if font.width == 0 || font.height == 0 {
+        bail!(
+            "Font size must be at least 1x1, is {}x{}",
+            font.width,
+            font.height
+        );
+    }
+    if font.width > MAX_SIZE || font.height > MAX_SIZE {
+        bail!(
+            "Font size can be at most {MAX_SIZE}x{MAX_SIZE}, is {}x{}",
+            font.width,
+            font.height
+        );
+    }
+    if font.glyphs.is_empty() {
+        bail!("Font has no glyphs");
+    }
+    if font.guide_layers.is_empty() {
+        bail!("Font has no guide layers");
+    }
+    if let Some(metrics) = &font.metrics {
+        if metrics.baseline >= font.height {
+            bail!(
+                "Baseline must be a row of the font (0 to {}), is {}",
+                font.height - 1,
+                metrics.baseline
+            );
+        }
+        for (name, value) in [
+            ("Ascent", metrics.ascent),
+            ("Descent", metrics.descent),
+            ("Cap height", metrics.cap_height),
+            ("X-height", metrics.x_height),
+        ] {
+            if value > font.height {
+                bail!(
+                    "{name} can be at most the font height ({}), is {value}",
+                    font.height
+                );
+            }
+        }
+    }
+    for (chr, glyph) in &font.glyphs {
+        let (width, height) = glyph.size();
+        if width == 0 || height == 0 {
+            bail!("Glyph {chr:?} must be at least 1x1, is {width}x{height}");
+        }
+        if width > MAX_SIZE || height > MAX_SIZE {
+            bail!("Glyph {chr:?} can be at most {MAX_SIZE}x{MAX_SIZE}, is {width}x{height}");
+        }
+        if glyph.dots.len() != width * height {
+            bail!(
+                "Glyph {chr:?} is {width}x{height} so should have {} dots, has {}",
+                width * height,
+                glyph.dots.len()
+            );
+        }
+        if glyph.guides.len() != font.guide_layers.len() {
+            bail!(
+                "Glyph {chr:?} should have guides for {} layers, has {}",
+                font.guide_layers.len(),
+                glyph.guides.len()
+            );
+        }
+        for (layer, guides) in font.guide_layers.iter().zip(&glyph.guides) {
+            if guides.len() != width * height {
+                bail!(
+                    "Glyph {chr:?} is {width}x{height} so should have {} guides on {}, has {}",
+                    width * height,
+                    layer.name,
+                    guides.len()
+                );
+            }
+        }
+    }
+    for layer in font.guide_layers.iter().filter(|layer| layer.shared) {
+        if layer.cells.len() != font.width * font.height {
+            bail!(
+                "Shared guide layer {} should have {} cells, has {}",
+                layer.name,
+                font.width * font.height,
+                layer.cells.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Metrics;
+    use serde_json::json;
+
+    #[test]
+    fn round_trip_keeps_guides() {
+        let mut font = Font::new(3, 3);
+        let glyph = font.glyphs.get_mut(&'A').unwrap();
+        glyph.dots[4] = true;
+        glyph.guides[0][6] = true;
+        assert_eq!(parse(&write(&font).unwrap()).unwrap(), font);
+    }
+
+    #[test]
+    fn migrates_legacy_glyph() {
+        let font = migrate(json!({
+            "width": 2,
+            "height": 1,
+            "dots": [true, false]
+        }))
+        .unwrap();
+        validate(&font).unwrap();
+        assert_eq!(font.glyphs[&LEGACY_CHAR].dots, [true, false]);
+        assert_eq!(font.glyphs[&LEGACY_CHAR].guides, [[false, false]]);
+    }
+
+    #[test]
+    fn migrates_unversioned_and_single_guide_list() {
+        let glyphs = json!({
+            "A": { "width": 2, "height": 1, "dots": [true, false], "guides": [false, true] }
+        });
+        let unversioned = migrate(json!({ "width": 2, "height": 1, "glyphs": glyphs })).unwrap();
+        validate(&unversioned).unwrap();
+        assert_eq!(unversioned.glyphs[&'A'].guides, [[false, true]]);
+
+        let version_1 = json!({
+            "version": 1,
+            "font": { "width": 2, "height": 1, "glyphs": glyphs }
+        });
+        assert_eq!(migrate(version_1).unwrap(), unversioned);
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let font = serde_json::to_value(Font::new(1, 1)).unwrap();
+        let newer = json!({ "version": VERSION + 1, "font": font });
+        assert!(migrate(newer).is_err());
+        assert!(migrate(json!({ "version": "2", "font": font })).is_err());
+        assert!(migrate(json!({ "font": font })).is_err());
+    }
+
+    #[test]
+    fn validate_checks_sizes() {
+        assert!(validate(&Font::new(1, 1)).is_ok());
+        assert!(validate(&Font::new(0, 1)).is_err());
+        assert!(validate(&Font::new(MAX_SIZE + 1, 1)).is_err());
+
+        let mut font = Font::new(2, 2);
+        font.glyphs.get_mut(&'A').unwrap().dots.pop();
+        assert!(validate(&font).is_err());
+
+        let mut font = Font::new(2, 2);
+        font.glyphs.get_mut(&'A').unwrap().guides[0].push(false);
+        assert!(validate(&font).is_err());
+
+        let mut font = Font::new(2, 2);
+        font.glyphs
+            .get_mut(&'A')
+            .unwrap()
+            .guides
+            .push(vec![false; 4]);
+        assert!(validate(&font).is_err());
+
+        let mut font = Font::new(2, 2);
+        font.guide_layers[0].shared = true;
+        assert!(validate(&font).is_err());
+    }
+
+    #[test]
+    fn validate_checks_metrics() {
+        let mut font = Font::new(2, 4);
+        font.metrics = Some(Metrics::new(4));
+        assert!(validate(&font).is_ok());
+
+        font.metrics = Some(Metrics {
+            baseline: 4,
+            ..Metrics::new(4)
+        });
+        assert!(validate(&font).is_err());
+
+        font.metrics = Some(Metrics {
+            x_height: 5,
+            ..Metrics::new(4)
+        });
+        assert!(validate(&font).is_err());
+    }
+}
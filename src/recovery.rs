@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use fontpad::font::Font;
+use fontpad::project;
+use pixels_graphics_lib::prelude::get_pref_dir;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "recovery.json";
+
+/// Unsaved work, written periodically so it can be restored after a crash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recovery {
+    pub font: Font,
+    pub current: char,
+    pub project_path: Option<String>,
+}
+
+/// Tracks whether the font has changes that would be lost if it were replaced
+///
+/// The baseline only moves when a project is saved or loaded, copying or exporting doesn't count
+#[derive(Debug)]
+pub struct Changes {
+    /// Font as last saved to or loaded from a project
+    saved: Font,
+    /// Font as last written to the recovery file
+    autosaved: Option<Font>,
+}
+
+impl Changes {
+    pub fn new(font: &Font) -> Changes {
+        Changes {
+            saved: font.clone(),
+            autosaved: None,
+        }
+    }
+}
+
+impl Changes {
+    /// Call after `font` has been written to or read from a project
+    pub fn set_saved(&mut self, font: &Font) {
+        self.saved = font.clone();
+        self.autosaved = None;
+    }
+
+    pub fn set_autosaved(&mut self, font: Font) {
+        self.autosaved = Some(font);
+    }
+
+    /// Returns true if `font` differs from the last saved or loaded project
+    pub fn is_unsaved(&self, font: &Font) -> bool {
+        &self.saved != font
+    }
+
+    /// Returns true if `font` is unsaved and not already in the recovery file
+    pub fn needs_autosave(&self, font: &Font) -> bool {
+        self.is_unsaved(font) && self.autosaved.as_ref() != Some(font)
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    let dir = get_pref_dir("app", "emmabritton", "fontpad")
+        .map_err(|err| anyhow::anyhow!("Unable to find settings dir: {err:?}"))?;
+    Ok(dir.join(FILE_NAME))
+}
+
+pub fn save(recovery: &Recovery) -> Result<()> {
+    let text = serde_json::to_string(recovery)?;
+    fs::write(path()?, text).context("Unable to write recovery file")
+}
+
+/// Returns None if there's nothing to recover
+pub fn load() -> Result<Option<Recovery>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(path).context("Unable to read recovery file")?;
+    let recovery: Recovery = serde_json::from_str(&text).context("Invalid recovery file")?;
+    project::validate(&recovery.font)?;
+    Ok(Some(recovery))
+}
+
+pub fn clear() -> Result<()> {
+    let path = path()?;
+    if path.exists() {
+        fs::remove_file(path).context("Unable to delete recovery file")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_are_unsaved_until_project_saved() {
+        let mut font = Font::new(2, 2);
+        let mut changes = Changes::new(&font);
+        assert!(!changes.is_unsaved(&font));
+
+        font.glyphs.get_mut(&'A').unwrap().dots[0] = true;
+        assert!(changes.is_unsaved(&font));
+        assert!(changes.needs_autosave(&font));
+
+        //autosaving (or copying) must not hide the changes from the discard prompt
+        changes.set_autosaved(font.clone());
+        assert!(changes.is_unsaved(&font));
+        assert!(!changes.needs_autosave(&font));
+
+        changes.set_saved(&font);
+        assert!(!changes.is_unsaved(&font));
+        assert!(!changes.needs_autosave(&font));
+    }
+}
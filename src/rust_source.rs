@@ -0,0 +1,651 @@
+use crate::font::{Font, Metrics};
+use crate::glyph::Glyph;
+use crate::guide::default_layers;
+use crate::project;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// Constant names used by buffer-graphics-lib font modules
+const NAMES: [(char, &str); 103] = [
+    (' ', "SPACE"),
+    ('!', "EXCLAIM"),
+    ('"', "DOUBLE_QUOTE"),
+    ('#', "HASH"),
+    ('$', "DOLLAR"),
+    ('%', "PERCENT"),
+    ('&', "AMPERSAND"),
+    ('\'', "QUOTE"),
+    ('(', "PAREN_L"),
+    (')', "PAREN_R"),
+    ('*', "ASTERISK"),
+    ('+', "PLUS"),
+    (',', "COMMA"),
+    ('-', "MINUS"),
+    ('.', "PERIOD"),
+    ('/', "SLASH"),
+    ('0', "ZERO"),
+    ('1', "ONE"),
+    ('2', "TWO"),
+    ('3', "THREE"),
+    ('4', "FOUR"),
+    ('5', "FIVE"),
+    ('6', "SIX"),
+    ('7', "SEVEN"),
+    ('8', "EIGHT"),
+    ('9', "NINE"),
+    (':', "COLON"),
+    (';', "SEMICOLON"),
+    ('<', "ANGLE_L"),
+    ('=', "EQUALS"),
+    ('>', "ANGLE_R"),
+    ('?', "QUESTION"),
+    ('@', "AT"),
+    ('A', "A"),
+    ('B', "B"),
+    ('C', "C"),
+    ('D', "D"),
+    ('E', "E"),
+    ('F', "F"),
+    ('G', "G"),
+    ('H', "H"),
+    ('I', "I"),
+    ('J', "J"),
+    ('K', "K"),
+    ('L', "L"),
+    ('M', "M"),
+    ('N', "N"),
+    ('O', "O"),
+    ('P', "P"),
+    ('Q', "Q"),
+    ('R', "R"),
+    ('S', "S"),
+    ('T', "T"),
+    ('U', "U"),
+    ('V', "V"),
+    ('W', "W"),
+    ('X', "X"),
+    ('Y', "Y"),
+    ('Z', "Z"),
+    ('[', "SQUARE_L"),
+    ('\\', "BACKSLASH"),
+    (']', "SQUARE_R"),
+    ('^', "POWER"),
+    ('_', "UNDERSCORE"),
+    ('`', "BACKTICK"),
+    ('a', "LOWER_A"),
+    ('b', "LOWER_B"),
+    ('c', "LOWER_C"),
+    ('d', "LOWER_D"),
+    ('e', "LOWER_E"),
+    ('f', "LOWER_F"),
+    ('g', "LOWER_G"),
+    ('h', "LOWER_H"),
+    ('i', "LOWER_I"),
+    ('j', "LOWER_J"),
+    ('k', "LOWER_K"),
+    ('l', "LOWER_L"),
+    ('m', "LOWER_M"),
+    ('n', "LOWER_N"),
+    ('o', "LOWER_O"),
+    ('p', "LOWER_P"),
+    ('q', "LOWER_Q"),
+    ('r', "LOWER_R"),
+    ('s', "LOWER_S"),
+    ('t', "LOWER_T"),
+    ('u', "LOWER_U"),
+    ('v', "LOWER_V"),
+    ('w', "LOWER_W"),
+    ('x', "LOWER_X"),
+    ('y', "LOWER_Y"),
+    ('z', "LOWER_Z"),
+    ('{', "CURLY_L"),
+    ('|', "PIPE"),
+    ('}', "CURLY_R"),
+    ('~', "TILDE"),
+    ('£', "POUND"),
+    ('¥', "YEN"),
+    ('¢', "CENT"),
+    ('¤', "CURRENCY"),
+    ('€', "EURO"),
+    ('°', "DEGREE"),
+    ('…', "ELLIPSIS"),
+    ('✓', "CHECK"),
+];
+
+/// buffer-graphics-lib codes for non ASCII characters
+const CODE_NAMES: [(char, &str, u8); 8] = [
+    ('£', "ASCII_POUND", 163),
+    ('¥', "ASCII_YEN", 165),
+    ('¢', "ASCII_CENT", 162),
+    ('¤', "ASCII_CURRENCY", 164),
+    ('€', "ASCII_EURO", 127),
+    ('°', "ASCII_DEGREE", 176),
+    ('…', "ASCII_ELLIPSIS", 31),
+    ('✓', "ASCII_CHECK", 25),
+];
+
+/// Character used for the glyph returned for unsupported codes
+pub const UNKNOWN_CHAR: char = char::REPLACEMENT_CHARACTER;
+const UNKNOWN_NAME: &str = "UNKNOWN";
+
+/// Constant name for `chr`, characters without a standard name use their code point
+pub fn const_name(chr: char) -> String {
+    if chr == UNKNOWN_CHAR {
+        return UNKNOWN_NAME.to_string();
+    }
+    NAMES
+        .iter()
+        .find(|(c, _)| *c == chr)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("U_{:04X}", chr as u32))
+}
+
+/// Match arm pattern for `chr` in `get_px_ascii` of `font`, None if buffer-graphics-lib can't render it
+///
+/// Space has no glyph in buffer-graphics-lib fonts, and ASCII characters are skipped if their code is
+/// used by a symbol in the font, e.g. DEL when the font has '€'
+fn code_pattern(font: &Font, chr: char) -> Option<String> {
+    if chr == ' ' {
+        None
+    } else if chr.is_ascii() {
+        let code = chr as u8;
+        let taken = CODE_NAMES.iter().any(|(symbol, _, symbol_code)| {
+            *symbol_code == code && font.glyphs.contains_key(symbol)
+        });
+        (!taken).then(|| code.to_string())
+    } else {
+        CODE_NAMES
+            .iter()
+            .find(|(c, _, _)| *c == chr)
+            .map(|(_, name, _)| name.to_string())
+    }
+}
+
+/// Generate a buffer-graphics-lib font module
+///
+/// All glyphs must be the same size, characters buffer-graphics-lib can't render are skipped (see [code_pattern]).
+/// The glyph for [UNKNOWN_CHAR] is used for unsupported codes, if the font doesn't have one
+/// it will be blank
+pub fn write(font: &Font) -> Result<String> {
+    let Some(first) = font.glyphs.values().next() else {
+        bail!("Font has no glyphs");
+    };
+    let (width, height) = (first.width, first.height);
+    if let Some((chr, glyph)) = font
+        .glyphs
+        .iter()
+        .find(|(_, glyph)| glyph.width != width || glyph.height != height)
+    {
+        bail!(
+            "'{chr}' is {}x{}, all glyphs must be {width}x{height}",
+            glyph.width,
+            glyph.height
+        );
+    }
+
+    let glyphs: Vec<(char, String, &Glyph)> = font
+        .glyphs
+        .iter()
+        .filter(|(&chr, _)| chr != UNKNOWN_CHAR)
+        .filter_map(|(&chr, glyph)| code_pattern(font, chr).map(|code| (chr, code, glyph)))
+        .collect();
+
+    let mut output = String::new();
+    let mut imports: Vec<&str> = CODE_NAMES
+        .iter()
+        .filter(|(chr, _, _)| font.glyphs.contains_key(chr))
+        .map(|(_, name, _)| *name)
+        .collect();
+    imports.sort();
+    if !imports.is_empty() {
+        let _ = writeln!(output, "use crate::text::{{{}}};\n", imports.join(", "));
+    }
+    let _ = writeln!(output, "pub const CHAR_WIDTH: usize = {width};");
+    let _ = writeln!(output, "pub const CHAR_HEIGHT: usize = {height};\n");
+    if let Some(metrics) = font.metrics {
+        let _ = writeln!(output, "pub const BASELINE: usize = {};", metrics.baseline);
+        let _ = writeln!(output, "pub const ASCENT: usize = {};", metrics.ascent);
+        let _ = writeln!(output, "pub const DESCENT: usize = {};", metrics.descent);
+        let _ = writeln!(
+            output,
+            "pub const CAP_HEIGHT: usize = {};",
+            metrics.cap_height
+        );
+        let _ = writeln!(
+            output,
+            "pub const X_HEIGHT: usize = {};\n",
+            metrics.x_height
+        );
+    }
+    let _ = writeln!(
+        output,
+        "pub const fn get_px_ascii(code: u8) -> &'static [bool] {{"
+    );
+    let _ = writeln!(output, "    match code {{");
+    for (chr, code, _) in &glyphs {
+        let _ = writeln!(output, "        {code} => &{},", const_name(*chr));
+    }
+    let _ = writeln!(output, "        _ => &{UNKNOWN_NAME},");
+    let _ = writeln!(output, "    }}");
+    let _ = writeln!(output, "}}\n");
+    let _ = writeln!(
+        output,
+        "pub const LETTER_PX_COUNT: usize = CHAR_WIDTH * CHAR_HEIGHT;\n"
+    );
+    for (chr, _, glyph) in &glyphs {
+        write_glyph(&mut output, &const_name(*chr), &glyph.dots, width);
+    }
+    let unknown = font
+        .glyphs
+        .get(&UNKNOWN_CHAR)
+        .map(|glyph| glyph.dots.clone())
+        .unwrap_or_else(|| vec![false; width * height]);
+    write_glyph(&mut output, UNKNOWN_NAME, &unknown, width);
+    Ok(output)
+}
+
+fn write_glyph(output: &mut String, name: &str, dots: &[bool], width: usize) {
+    let _ = writeln!(output, "const {name}: [bool; LETTER_PX_COUNT] = [");
+    for row in dots.chunks(width) {
+        let row: Vec<&str> = row
+            .iter()
+            .map(|&dot| if dot { "true" } else { "false" })
+            .collect();
+        let _ = writeln!(output, "    {},", row.join(", "));
+    }
+    let _ = writeln!(output, "];");
+}
+
+/// Load glyphs from Rust source containing `const NAME: [bool; N] = [..];` arrays
+///
+/// Characters are taken from match arms (`65 => &A`, `'a' => &LOWER_A`, `ASCII_EURO => &EURO`)
+/// if there are any, otherwise from the constant names buffer-graphics-lib uses.
+/// The glyph size is read from `CHAR_WIDTH` and `CHAR_HEIGHT`, metrics from `BASELINE`, `ASCENT`,
+/// `DESCENT`, `CAP_HEIGHT` and `X_HEIGHT` if they're all present
+pub fn parse(text: &str) -> Result<Font> {
+    let text: String = text
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let arrays = parse_arrays(&text);
+    if arrays.is_empty() {
+        bail!("No [bool; N] constants found");
+    }
+    let first_len = arrays.values().next().map(|dots| dots.len()).unwrap_or(0);
+    let (width, height) = match (
+        parse_usize_const(&text, "CHAR_WIDTH"),
+        parse_usize_const(&text, "CHAR_HEIGHT"),
+    ) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) if width > 0 => (width, first_len / width),
+        (None, Some(height)) if height > 0 => (first_len / height, height),
+        _ => bail!("Unable to find glyph size, CHAR_WIDTH and CHAR_HEIGHT are missing"),
+    };
+    if width == 0 || height == 0 {
+        bail!("Invalid glyph size {width}x{height}");
+    }
+
+    let arms = parse_arms(&text);
+    let mapping: Vec<(char, &str)> = if arms.is_empty() {
+        arrays
+            .keys()
+            .filter_map(|name| char_for_name(name).map(|chr| (chr, name.as_str())))
+            .collect()
+    } else {
+        arms.iter()
+            .map(|(chr, name)| (*chr, name.as_str()))
+            .collect()
+    };
+
+    let mut glyphs = BTreeMap::new();
+    for (chr, name) in mapping {
+        let Some(dots) = arrays.get(name) else {
+            continue;
+        };
+        if dots.len() != width * height {
+            bail!(
+                "{name} has {} values, expected {} ({width}x{height})",
+                dots.len(),
+                width * height
+            );
+        }
+        glyphs.insert(
+            chr,
+            Glyph {
+                width,
+                height,
+                dots: dots.clone(),
+                guides: vec![vec![false; dots.len()]],
+            },
+        );
+    }
+    if glyphs.is_empty() {
+        bail!("Unable to match any constants to characters");
+    }
+
+    let metrics = match ["BASELINE", "ASCENT", "DESCENT", "CAP_HEIGHT", "X_HEIGHT"]
+        .map(|name| parse_usize_const(&text, name))
+    {
+        [Some(baseline), Some(ascent), Some(descent), Some(cap_height), Some(x_height)] => {
+            Some(Metrics {
+                baseline,
+                ascent,
+                descent,
+                cap_height,
+                x_height,
+            })
+        }
+        _ => None,
+    };
+
+    let font = Font {
+        width,
+        height,
+        glyphs,
+        metrics,
+        guide_layers: default_layers(),
+    };
+    project::validate(&font)?;
+    Ok(font)
+}
+
+fn char_for_name(name: &str) -> Option<char> {
+    if name == UNKNOWN_NAME {
+        return Some(UNKNOWN_CHAR);
+    }
+    if let Some(code) = name.strip_prefix("U_") {
+        return u32::from_str_radix(code, 16).ok().and_then(char::from_u32);
+    }
+    NAMES.iter().find(|(_, n)| *n == name).map(|(chr, _)| *chr)
+}
+
+fn skip_ident(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+/// Value of `const NAME: usize = N;`, names that only start with `name` are skipped
+fn parse_usize_const(text: &str, name: &str) -> Option<usize> {
+    let mut rest = text;
+    while let Some(idx) = rest.find("const") {
+        let at_boundary = !rest[..idx].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        rest = &rest[idx + "const".len()..];
+        if !at_boundary || !rest.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let (ident, after) = skip_ident(rest.trim_start());
+        let Some(after) = after.trim_start().strip_prefix(':') else {
+            continue;
+        };
+        if ident != name {
+            continue;
+        }
+        let eq = after.find('=')?;
+        if after[..eq].contains(';') {
+            return None;
+        }
+        let value = &after[eq + 1..];
+        return value[..value.find(';')?].trim().parse().ok();
+    }
+    None
+}
+
+/// Find all `const NAME: [bool; N] = [..];` (or static) arrays
+fn parse_arrays(text: &str) -> HashMap<String, Vec<bool>> {
+    let mut output = HashMap::new();
+    let mut rest = text;
+    while let Some(idx) = rest.find(|c: char| c.is_alphabetic()) {
+        rest = &rest[idx..];
+        let (word, after) = skip_ident(rest);
+        rest = after;
+        if word != "const" && word != "static" {
+            continue;
+        }
+        let (name, after) = skip_ident(after.trim_start());
+        let after = after.trim_start();
+        let Some(after) = after.strip_prefix(':') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let Some(after) = after.strip_prefix('[') else {
+            continue;
+        };
+        if !after.trim_start().starts_with("bool") {
+            continue;
+        }
+        let Some(eq) = after.find('=') else {
+            continue;
+        };
+        let Some(end) = after[eq..].find(';').map(|end| eq + end) else {
+            continue;
+        };
+        let Some(open) = after[eq..end].find('[') else {
+            continue;
+        };
+        let Some(close) = after[eq..end].rfind(']') else {
+            continue;
+        };
+        let values: Option<Vec<bool>> = after[eq + open + 1..eq + close]
+            .split(',')
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(|value| match value {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ => None,
+            })
+            .collect();
+        if let Some(values) = values {
+            output.insert(name.to_string(), values);
+        }
+        rest = &after[end..];
+    }
+    output
+}
+
+/// Find all match arms like `65 => &A` or `'a' | 'A' => &A`, unsupported patterns are ignored
+fn parse_arms(text: &str) -> Vec<(char, String)> {
+    let mut output = vec![];
+    for line in text.lines() {
+        let Some((pattern, target)) = line.split_once("=>") else {
+            continue;
+        };
+        let target = target.trim_start().trim_start_matches('&');
+        let (name, _) = skip_ident(target);
+        if name.is_empty() {
+            continue;
+        }
+        let pattern = pattern.rsplit_once('{').map(|(_, p)| p).unwrap_or(pattern);
+        for chr in split_pattern(pattern.trim())
+            .into_iter()
+            .filter_map(parse_pattern)
+        {
+            output.push((chr, name.to_string()));
+        }
+    }
+    output
+}
+
+/// Split `a | b` outside of char literals
+fn split_pattern(pattern: &str) -> Vec<&str> {
+    let mut output = vec![];
+    let mut in_quote = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, chr) in pattern.char_indices() {
+        match chr {
+            '\\' if in_quote => escaped = !escaped,
+            '\'' if !escaped => in_quote = !in_quote,
+            '|' if !in_quote => {
+                output.push(pattern[start..i].trim());
+                start = i + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    output.push(pattern[start..].trim());
+    output
+}
+
+fn parse_pattern(pattern: &str) -> Option<char> {
+    if pattern == "_" {
+        return Some(UNKNOWN_CHAR);
+    }
+    if let Some((chr, _, _)) = CODE_NAMES.iter().find(|(_, name, _)| *name == pattern) {
+        return Some(*chr);
+    }
+    if let Ok(code) = pattern.parse::<u8>() {
+        if let Some((chr, _, _)) = CODE_NAMES.iter().find(|(_, _, c)| *c == code) {
+            return Some(*chr);
+        }
+        return Some(code as char);
+    }
+    let literal = pattern.trim_start_matches('b');
+    if let Some(inner) = literal
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        let mut chars = inner.chars();
+        let chr = match (chars.next(), chars.next()) {
+            (Some('\\'), Some(escaped)) => match escaped {
+                'n' => '\n',
+                't' => '\t',
+                '0' => '\0',
+                other => other,
+            },
+            (Some(chr), None) => chr,
+            _ => return None,
+        };
+        return Some(chr);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glyph::MAX_SIZE;
+
+    /// 2x1 glyphs for an ASCII letter, a symbol with a buffer-graphics-lib code and one without
+    fn font() -> Font {
+        let glyph = |dots: [bool; 2]| Glyph {
+            dots: dots.to_vec(),
+            ..Glyph::new(2, 1)
+        };
+        Font {
+            glyphs: BTreeMap::from([
+                ('A', glyph([true, false])),
+                ('€', glyph([false, true])),
+                ('ā', glyph([true, true])),
+            ]),
+            ..Font::new(2, 1)
+        }
+    }
+
+    #[test]
+    fn writes_codes_for_supported_chars() {
+        let code = write(&font()).unwrap();
+        assert!(code.starts_with("use crate::text::{ASCII_EURO};"));
+        assert!(code.contains("65 => &A,"));
+        assert!(code.contains("ASCII_EURO => &EURO,"));
+        assert!(code.contains("const EURO: [bool; LETTER_PX_COUNT] = [\n    false, true,\n];"));
+        assert!(!code.contains("U_0101"));
+    }
+
+    #[test]
+    fn skips_space_and_codes_used_by_symbols() {
+        let mut font = font();
+        font.set_glyph(' ', Glyph::new(2, 1));
+        font.set_glyph('\x7f', Glyph::new(2, 1));
+        font.set_glyph('\x19', Glyph::new(2, 1));
+        let code = write(&font).unwrap();
+        assert!(!code.contains("32 =>"));
+        assert!(!code.contains("SPACE"));
+        assert!(!code.contains("127 =>"));
+        assert!(code.contains("25 => &U_0019,"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let font = font();
+        let parsed = parse(&write(&font).unwrap()).unwrap();
+        assert_eq!((parsed.width, parsed.height), (2, 1));
+        assert_eq!(parsed.glyphs[&'A'], font.glyphs[&'A']);
+        assert_eq!(parsed.glyphs[&'€'], font.glyphs[&'€']);
+        assert!(!parsed.glyphs.contains_key(&'ā'));
+    }
+
+    #[test]
+    fn metrics_round_trip() {
+        let mut font = font();
+        font.metrics = Some(Metrics::new(1));
+        let parsed = parse(&write(&font).unwrap()).unwrap();
+        assert_eq!(parsed.metrics, font.metrics);
+    }
+
+    #[test]
+    fn arms_can_share_a_glyph() {
+        let text = "
+            const CHAR_WIDTH: usize = 1;
+            const CHAR_HEIGHT: usize = 1;
+            match code {
+                'x' | 121 => &MARK,
+                _ => &UNKNOWN,
+            }
+            const MARK: [bool; 1] = [true];
+        ";
+        let font = parse(text).unwrap();
+        assert_eq!(font.glyphs[&'x'].dots, [true]);
+        assert_eq!(font.glyphs[&'y'].dots, [true]);
+    }
+
+    #[test]
+    fn write_needs_same_size_glyphs() {
+        let mut font = font();
+        font.set_glyph('B', Glyph::new(2, 2));
+        assert!(write(&font).is_err());
+    }
+
+    #[test]
+    fn const_names_match_whole_words() {
+        let text = "
+            pub const CHAR_WIDTHS: [usize; 2] = [1, 2];
+            pub const X_HEIGHTS: [usize; 1] = [1];
+            pub const CHAR_WIDTH: usize = 2;
+            pub const CHAR_HEIGHT: usize = 1;
+            pub const X_HEIGHT: usize = 1;
+        ";
+        assert_eq!(parse_usize_const(text, "CHAR_WIDTH"), Some(2));
+        assert_eq!(parse_usize_const(text, "X_HEIGHT"), Some(1));
+        assert_eq!(parse_usize_const(text, "HEIGHT"), None);
+        assert_eq!(parse_usize_const(text, "ASCENT"), None);
+    }
+
+    #[test]
+    fn parses_source_with_prefixed_consts() {
+        let text = "
+            pub const CHAR_WIDTHS: [usize; 2] = [1, 2];
+            pub const CHAR_WIDTH: usize = 2;
+            pub const CHAR_HEIGHT: usize = 1;
+            const A: [bool; 2] = [true, false];
+        ";
+        let font = parse(text).unwrap();
+        assert_eq!(font.size(), (2, 1));
+        assert_eq!(font.glyphs[&'A'].dots, [true, false]);
+    }
+
+    #[test]
+    fn rejects_oversized_glyphs() {
+        let values = vec!["false"; MAX_SIZE + 1].join(",");
+        let text = format!(
+            "const CHAR_WIDTH: usize = {};\nconst CHAR_HEIGHT: usize = 1;\nconst A: [bool; {}] = [{values}];",
+            MAX_SIZE + 1,
+            MAX_SIZE + 1
+        );
+        assert!(parse(&text).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::glyph::Region;
+
+/// Cells on a line between `from` and `to` (inclusive), using Bresenham's algorithm
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (end_x, end_y) = (to.0 as isize, to.1 as isize);
+    let dx = (end_x - x).abs();
+    let dy = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = vec![];
+    loop {
+        cells.push((x as usize, y as usize));
+        if x == end_x && y == end_y {
+            break;
+        }
+        let err2 = err * 2;
+        if err2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if err2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+    cells
+}
+
+/// Cells of a rectangle covering `region`, if not `filled` only the edges
+pub fn rect(region: Region, filled: bool) -> Vec<(usize, usize)> {
+    cells_in(region, |x, y| {
+        filled
+            || x == region.x
+            || y == region.y
+            || x == region.x + region.width - 1
+            || y == region.y + region.height - 1
+    })
+}
+
+/// Cells of the largest ellipse that fits in `region`, if not `filled` only the outline
+///
+/// A cell is inside the ellipse if its center is
+pub fn ellipse(region: Region, filled: bool) -> Vec<(usize, usize)> {
+    let center_x = (region.width as f64 - 1.0) / 2.0;
+    let center_y = (region.height as f64 - 1.0) / 2.0;
+    let radius_x = region.width as f64 / 2.0;
+    let radius_y = region.height as f64 / 2.0;
+    let inside = |x: isize, y: isize| {
+        let dx = (x as f64 - center_x) / radius_x;
+        let dy = (y as f64 - center_y) / radius_y;
+        dx * dx + dy * dy <= 1.0
+    };
+    cells_in(region, |x, y| {
+        let (x, y) = ((x - region.x) as isize, (y - region.y) as isize);
+        inside(x, y)
+            && (filled
+                || !inside(x - 1, y)
+                || !inside(x + 1, y)
+                || !inside(x, y - 1)
+                || !inside(x, y + 1))
+    })
+}
+
+/// Cells connected to `start` that have the same value, in a grid of `cells` that is `width` wide
+///
+/// If `diagonal` cells touching at corners are connected (8-connected), otherwise only cells sharing an edge are (4-connected)
+pub fn flood(
+    cells: &[bool],
+    width: usize,
+    start: (usize, usize),
+    diagonal: bool,
+) -> Vec<(usize, usize)> {
+    let height = cells.len() / width.max(1);
+    if start.0 >= width || start.1 >= height {
+        return vec![];
+    }
+    let value = cells[start.0 + start.1 * width];
+    let mut visited = vec![false; cells.len()];
+    let mut pending = vec![start];
+    let mut output = vec![];
+    visited[start.0 + start.1 * width] = true;
+    while let Some((x, y)) = pending.pop() {
+        output.push((x, y));
+        for (dx, dy) in [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (1, -1),
+            (-1, 1),
+            (1, 1),
+        ]
+        .into_iter()
+        .take(if diagonal { 8 } else { 4 })
+        {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let idx = nx as usize + ny as usize * width;
+            if !visited[idx] && cells[idx] == value {
+                visited[idx] = true;
+                pending.push((nx as usize, ny as usize));
+            }
+        }
+    }
+    output
+}
+
+fn cells_in<F: Fn(usize, usize) -> bool>(region: Region, include: F) -> Vec<(usize, usize)> {
+    let mut cells = vec![];
+    for y in region.y..region.y + region.height {
+        for x in region.x..region.x + region.width {
+            if include(x, y) {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn line_includes_both_ends() {
+        assert_eq!(line((0, 0), (3, 0)), [(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(line((2, 2), (0, 0)), [(2, 2), (1, 1), (0, 0)]);
+        assert_eq!(line((1, 1), (1, 1)), [(1, 1)]);
+    }
+
+    #[test]
+    fn rect_outline_and_filled() {
+        let region = Region::new(0, 0, 3, 3);
+        assert_eq!(rect(region, true).len(), 9);
+        assert!(!rect(region, false).contains(&(1, 1)));
+        assert_eq!(rect(region, false).len(), 8);
+    }
+
+    #[test]
+    fn ellipse_fits_region() {
+        let region = Region::new(1, 1, 5, 3);
+        let cells = ellipse(region, true);
+        assert!(cells.iter().all(|&(x, y)| region.contains(x, y)));
+        assert!(cells.contains(&(3, 2)));
+        assert!(!cells.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn flood_follows_neighbours() {
+        // #.
+        // .#
+        let cells = [true, false, false, true];
+        assert_eq!(flood(&cells, 2, (0, 0), false), [(0, 0)]);
+        assert_eq!(sorted(flood(&cells, 2, (0, 0), true)), [(0, 0), (1, 1)]);
+        assert_eq!(flood(&cells, 2, (1, 0), false), [(1, 0)]);
+        assert_eq!(sorted(flood(&cells, 2, (1, 0), true)), [(0, 1), (1, 0)]);
+        assert!(flood(&cells, 2, (2, 0), false).is_empty());
+    }
+}